@@ -15,9 +15,12 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::cmp;
+use std::collections::HashMap;
 use std::collections::HashSet;
+use std::collections::VecDeque;
 use std::fs;
 use std::hash::Hasher;
+use std::io;
 use std::io::{Read, Write};
 use std::ops::Deref;
 use std::ops::DerefMut;
@@ -39,7 +42,7 @@ use chainstate::burn::ConsensusHash;
 use chainstate::stacks::TransactionPayload;
 use chainstate::stacks::{
     db::blocks::MemPoolRejection, db::ClarityTx, db::StacksChainState, db::TxStreamData,
-    index::Error as MarfError, Error as ChainstateError, StacksTransaction,
+    index::Error as MarfError, Error as ChainstateError, StacksBlock, StacksTransaction,
 };
 use core::FIRST_BURNCHAIN_CONSENSUS_HASH;
 use core::FIRST_STACKS_BLOCK_HASH;
@@ -75,6 +78,39 @@ use crate::types::chainstate::{BlockHeaderHash, StacksAddress, StacksBlockHeader
 pub const MEMPOOL_MAX_TRANSACTION_AGE: u64 = 256;
 pub const MAXIMUM_MEMPOOL_TX_CHAINING: u64 = 25;
 
+// how long, in seconds, a txid stays in the blacklist before it can be reconsidered for
+// admission.  This bounds how long a transaction that was only transiently invalid (e.g.
+// dropped because of a reorg) is kept out of the mempool.
+pub const DEFAULT_BLACKLIST_TIMEOUT: u64 = 24 * 60 * 60;
+
+// default cap, in bytes, on the total serialized size of all transactions held in `mempool`.
+// Once the running total (see `MemPoolDB::get_mempool_bytes`) would exceed this, `try_add_tx`
+// evicts the lowest fee-rate transactions to make room (see `MemPoolDB::evict_txs_for_space`).
+pub const DEFAULT_MAX_MEMPOOL_BYTES: u64 = 1024 * 1024 * 1024;
+
+// default minimum percentage by which a replacement transaction's fee rate must beat the
+// incumbent's to be accepted as a replace-by-fee (see `MemPoolDB::passes_min_rbf_bump`).
+pub const DEFAULT_MIN_RBF_FEE_RATE_BUMP_PCT: u64 = 5;
+
+// default minimum increment, in fee-per-byte, by which a replacement transaction's fee rate must
+// beat the incumbent's under `RbfRequirement::FeeRateIncrement` (see
+// `MemPoolDB::passes_min_rbf_bump_increment`).  Named after the "incremental relay fee" concept
+// Bitcoin Core's mempool RBF policy uses for the same purpose.
+pub const DEFAULT_MIN_RBF_FEE_RATE_INCREMENT: u64 = 1;
+
+// default time-to-live, in seconds, for a transaction sitting in the mempool (see
+// `MemPoolDB::garbage_collect_expired`).  Bounds how long a transaction whose nonce never
+// becomes reachable -- e.g. because the account's on-chain nonce raced ahead of it on a fork
+// this node never saw -- can occupy mempool space and bloom counter capacity.
+pub const DEFAULT_MEMPOOL_TX_TTL: u64 = 8 * 60 * 60;
+
+// default maximum distance an origin or sponsor nonce may sit from an account's confirmed nonce
+// before `MemPoolDB::garbage_collect_stale_nonces` considers it unreachable and evicts it.
+// Mirrors `MAXIMUM_MEMPOOL_TX_CHAINING`, the same cap `iterate_candidates` uses to decide how far
+// ahead of the confirmed nonce it will ever walk -- a tx past that point can never be offered up
+// to a miner.
+pub const DEFAULT_MAX_NONCE_GAP: u64 = MAXIMUM_MEMPOOL_TX_CHAINING;
+
 // name of table for storing the counting bloom filter
 pub const BLOOM_COUNTER_TABLE: &'static str = "txid_bloom_counter";
 
@@ -92,13 +128,41 @@ pub const BLOOM_COUNTER_DEPTH: usize = 2;
 // loading the bloom filter, even though the bloom filter is larger.
 const DEFAULT_MAX_TX_TAGS: u32 = 2048;
 
+// maximum number of recent txids we'll send as an exact `MemPoolSyncData::Inventory` list before
+// falling back to the (smaller, per-entry) tag set.  A raw Txid costs 32 bytes versus a TxTag's
+// 8, so this threshold is set well below `DEFAULT_MAX_TX_TAGS` -- an exact list only pays for
+// itself while the recent set is small, but it's worth it there since it has zero false
+// positives.  See `MemPoolDB::make_mempool_sync_data`.
+const DEFAULT_MAX_INVENTORY_TXS: u32 = 256;
+
+// default number of txids that `RecentRejects` remembers at once.  See
+// `MemPoolDB::set_max_recent_rejects`.
+pub const DEFAULT_MAX_RECENT_REJECTS: usize = 4096;
+
 #[derive(Debug, Clone, PartialEq, Hash, Eq)]
 pub struct TxTag(pub [u8; 8]);
 
 impl TxTag {
+    /// Derive the pair of SipHash-2-4 keys a given seed produces.  Expanding the seed into a key
+    /// pair this way -- rather than simply writing the seed as a message prefix into a
+    /// default-keyed hasher -- means `from_seed_and_txid` is a proper keyed PRF over `txid`: an
+    /// adversary who doesn't know `seed` can't predict the tag a txid will get, which is what
+    /// stops them from crafting txids that collide in a peer's bloom filter/tag set.
+    fn siphash_keys(seed: &[u8]) -> (u64, u64) {
+        let mut k0_hasher = SipHasher::new();
+        k0_hasher.write(seed);
+        k0_hasher.write(&[0u8]);
+
+        let mut k1_hasher = SipHasher::new();
+        k1_hasher.write(seed);
+        k1_hasher.write(&[1u8]);
+
+        (k0_hasher.finish(), k1_hasher.finish())
+    }
+
     pub fn from_seed_and_txid(seed: &[u8], txid: &Txid) -> TxTag {
-        let mut hasher = SipHasher::new();
-        hasher.write(seed);
+        let (k0, k1) = TxTag::siphash_keys(seed);
+        let mut hasher = SipHasher::new_with_keys(k0, k1);
         hasher.write(&txid.0);
 
         let result_64 = hasher.finish();
@@ -125,9 +189,43 @@ impl StacksMessageCodec for TxTag {
     }
 }
 
+/// How `try_add_tx` decides whether a replacement transaction's fee rate beats a same-fork
+/// conflict's by enough to replace it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RbfRequirement {
+    /// The replacement's fee rate must exceed the incumbent's by at least a percentage (see
+    /// `MemPoolAdmitter::min_rbf_fee_rate_bump_pct` and `MemPoolDB::passes_min_rbf_bump`).
+    FeeRatePercentage,
+    /// The replacement's fee rate must exceed the incumbent's by at least a flat per-byte
+    /// increment (see `MemPoolAdmitter::min_rbf_fee_rate_increment` and
+    /// `MemPoolDB::passes_min_rbf_bump_increment`), mirroring Bitcoin Core's incremental relay
+    /// fee policy for RBF.
+    FeeRateIncrement,
+}
+
 pub struct MemPoolAdmitter {
     cur_block: BlockHeaderHash,
     cur_consensus_hash: ConsensusHash,
+    /// When set, `tx_submit` will only admit a transaction directly into `mempool` if its
+    /// origin nonce exactly matches the account's confirmed nonce; anything strictly ahead of
+    /// that is deferred into `pending_mempool` instead of being admitted (or rejected outright
+    /// for exceeding `MAXIMUM_MEMPOOL_TX_CHAINING`, as the default mode does).
+    strict_sequential_admission: bool,
+    /// Cap, in bytes, on the total serialized size of all transactions held in `mempool`.  See
+    /// `MemPoolDB::evict_txs_for_space`.
+    max_mempool_bytes: u64,
+    /// Which of `RbfRequirement`'s comparisons `try_add_tx` uses to decide whether a replacement
+    /// beats a same-fork conflict.
+    rbf_requirement: RbfRequirement,
+    /// Minimum percentage by which a replacement transaction's fee rate must beat the
+    /// incumbent's for `try_add_tx` to accept it as a replace-by-fee.  Only consulted when
+    /// `rbf_requirement` is `RbfRequirement::FeeRatePercentage`.  See
+    /// `MemPoolDB::passes_min_rbf_bump`.
+    min_rbf_fee_rate_bump_pct: u64,
+    /// Minimum flat per-byte increment by which a replacement transaction's fee rate must beat
+    /// the incumbent's.  Only consulted when `rbf_requirement` is
+    /// `RbfRequirement::FeeRateIncrement`.  See `MemPoolDB::passes_min_rbf_bump_increment`.
+    min_rbf_fee_rate_increment: u64,
 }
 
 enum MemPoolWalkResult {
@@ -141,6 +239,11 @@ impl MemPoolAdmitter {
         MemPoolAdmitter {
             cur_block,
             cur_consensus_hash,
+            strict_sequential_admission: false,
+            max_mempool_bytes: DEFAULT_MAX_MEMPOOL_BYTES,
+            rbf_requirement: RbfRequirement::FeeRatePercentage,
+            min_rbf_fee_rate_bump_pct: DEFAULT_MIN_RBF_FEE_RATE_BUMP_PCT,
+            min_rbf_fee_rate_increment: DEFAULT_MIN_RBF_FEE_RATE_INCREMENT,
         }
     }
 
@@ -148,12 +251,50 @@ impl MemPoolAdmitter {
         self.cur_consensus_hash = cur_consensus_hash.clone();
         self.cur_block = cur_block.clone();
     }
+
+    /// Configure whether this admitter defers out-of-order transactions into the
+    /// `pending_mempool` holding area (see `MemPoolDB::get_next_nonce` and
+    /// `MemPoolDB::promote_pending_tx`) instead of admitting any tx within the usual chaining
+    /// window directly into `mempool`.
+    pub fn set_strict_sequential_admission(&mut self, enabled: bool) {
+        self.strict_sequential_admission = enabled;
+    }
+
+    /// Configure the cap on the total serialized size of all transactions held in `mempool`
+    /// before `try_add_tx` starts evicting the lowest fee-rate transactions to make room.
+    pub fn set_max_mempool_bytes(&mut self, max_mempool_bytes: u64) {
+        self.max_mempool_bytes = max_mempool_bytes;
+    }
+
+    /// Configure the minimum percentage by which a replacement transaction's fee rate must beat
+    /// the incumbent's for it to be accepted as a replace-by-fee.
+    pub fn set_min_rbf_fee_rate_bump_pct(&mut self, min_rbf_fee_rate_bump_pct: u64) {
+        self.min_rbf_fee_rate_bump_pct = min_rbf_fee_rate_bump_pct;
+    }
+
+    /// Configure the minimum flat per-byte increment by which a replacement transaction's fee
+    /// rate must beat the incumbent's.  Only takes effect once `rbf_requirement` is set to
+    /// `RbfRequirement::FeeRateIncrement` (see `set_rbf_requirement`).
+    pub fn set_min_rbf_fee_rate_increment(&mut self, min_rbf_fee_rate_increment: u64) {
+        self.min_rbf_fee_rate_increment = min_rbf_fee_rate_increment;
+    }
+
+    /// Configure which of `RbfRequirement`'s comparisons `try_add_tx` uses to decide whether a
+    /// replacement transaction beats a same-fork conflict.
+    pub fn set_rbf_requirement(&mut self, rbf_requirement: RbfRequirement) {
+        self.rbf_requirement = rbf_requirement;
+    }
+
+    /// Will chainstate admit this transaction, and if so, what is the origin account's current
+    /// nonce?  The admitter's own checks (nonce replay, balance, post-conditions, etc.) are done
+    /// by `chainstate`; the returned nonce lets the caller additionally bound how far ahead of
+    /// that nonce the mempool is willing to let this origin chain transactions.
     pub fn will_admit_tx(
         &mut self,
         chainstate: &mut StacksChainState,
         tx: &StacksTransaction,
         tx_size: u64,
-    ) -> Result<(), MemPoolRejection> {
+    ) -> Result<u64, MemPoolRejection> {
         chainstate.will_admit_mempool_tx(&self.cur_consensus_hash, &self.cur_block, tx, tx_size)
     }
 }
@@ -163,6 +304,9 @@ pub enum MemPoolDropReason {
     REPLACE_BY_FEE,
     STALE_COLLECT,
     TOO_EXPENSIVE,
+    EVICTED_LOW_FEE,
+    STALE_NONCE,
+    TTL_EXPIRED,
 }
 
 impl std::fmt::Display for MemPoolDropReason {
@@ -172,6 +316,9 @@ impl std::fmt::Display for MemPoolDropReason {
             MemPoolDropReason::TOO_EXPENSIVE => write!(f, "TooExpensive"),
             MemPoolDropReason::REPLACE_ACROSS_FORK => write!(f, "ReplaceAcrossFork"),
             MemPoolDropReason::REPLACE_BY_FEE => write!(f, "ReplaceByFee"),
+            MemPoolDropReason::EVICTED_LOW_FEE => write!(f, "EvictedLowFee"),
+            MemPoolDropReason::STALE_NONCE => write!(f, "StaleNonce"),
+            MemPoolDropReason::TTL_EXPIRED => write!(f, "TtlExpired"),
         }
     }
 }
@@ -180,6 +327,428 @@ pub trait MemPoolEventDispatcher {
     fn mempool_txs_dropped(&self, txids: Vec<Txid>, reason: MemPoolDropReason);
 }
 
+/// The deterministic, tip-dependent-only rejection reasons that `RecentRejects` will remember.
+/// These are exactly the `MemPoolRejection` variants `tx_submit` can raise purely from looking at
+/// the transaction and the mempool's own nonce bookkeeping, with no remaining ambiguity to
+/// reconstruct on a cache hit -- as opposed to e.g. a balance check, which depends on chainstate
+/// that the admitter still needs to consult fresh every time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CacheableRejection {
+    ConflictingNonceInMempool,
+    TooMuchChaining,
+}
+
+impl CacheableRejection {
+    fn from_rejection(rejection: &MemPoolRejection) -> Option<CacheableRejection> {
+        match rejection {
+            MemPoolRejection::ConflictingNonceInMempool => {
+                Some(CacheableRejection::ConflictingNonceInMempool)
+            }
+            MemPoolRejection::TooMuchChaining(..) => Some(CacheableRejection::TooMuchChaining),
+            _ => None,
+        }
+    }
+
+    /// Rebuild the original `MemPoolRejection` using the data `tx_submit` already has on hand
+    /// for this resubmission (the origin address/nonce are deterministic functions of the tx
+    /// itself, so they're the same now as when the rejection was first cached).
+    fn to_rejection(&self, origin_address: &StacksAddress, origin_nonce: u64) -> MemPoolRejection {
+        match self {
+            CacheableRejection::ConflictingNonceInMempool => {
+                MemPoolRejection::ConflictingNonceInMempool
+            }
+            CacheableRejection::TooMuchChaining => {
+                MemPoolRejection::TooMuchChaining(origin_address.clone(), origin_nonce)
+            }
+        }
+    }
+}
+
+/// An in-memory, size-bounded cache of txids that `tx_submit` has already rejected against a
+/// specific chain tip, so that a transaction re-broadcast by the network (or resubmitted by an
+/// impatient client) isn't repeatedly re-run through `MemPoolAdmitter::will_admit_tx`.  Only
+/// deterministic rejections (see `CacheableRejection`) are kept -- the whole cache is thrown away
+/// as soon as `tx_submit` is called against a `(consensus_hash, block_hash)` tip different from
+/// the one it was built against, since a tx rejected for a nonce reason at the old tip may be
+/// perfectly valid at the new one.
+pub struct RecentRejects {
+    tip: Option<(ConsensusHash, BlockHeaderHash)>,
+    order: VecDeque<Txid>,
+    members: HashMap<Txid, CacheableRejection>,
+    max_size: usize,
+}
+
+impl RecentRejects {
+    pub fn new(max_size: usize) -> RecentRejects {
+        RecentRejects {
+            tip: None,
+            order: VecDeque::new(),
+            members: HashMap::new(),
+            max_size,
+        }
+    }
+
+    /// Forget every cached rejection if `consensus_hash`/`block_hash` differ from the tip this
+    /// cache was last populated against -- a tx rejected for a nonce reason at the old tip may be
+    /// perfectly valid at the new one.
+    fn reset_if_new_tip(&mut self, consensus_hash: &ConsensusHash, block_hash: &BlockHeaderHash) {
+        let tip = (consensus_hash.clone(), block_hash.clone());
+        if self.tip.as_ref() != Some(&tip) {
+            self.order.clear();
+            self.members.clear();
+            self.tip = Some(tip);
+        }
+    }
+
+    /// If `txid` has already been rejected for a deterministic reason against this exact tip,
+    /// return the `MemPoolRejection` it was rejected with.
+    fn check(
+        &mut self,
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+        txid: &Txid,
+        origin_address: &StacksAddress,
+        origin_nonce: u64,
+    ) -> Option<MemPoolRejection> {
+        self.reset_if_new_tip(consensus_hash, block_hash);
+        self.members
+            .get(txid)
+            .map(|cached| cached.to_rejection(origin_address, origin_nonce))
+    }
+
+    /// Record that `txid` was rejected against this tip, evicting the oldest entry if the cache
+    /// is now over `max_size`.  No-op if `rejection` isn't one of the deterministic reasons this
+    /// cache tracks (see `CacheableRejection`).
+    fn record(
+        &mut self,
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+        txid: Txid,
+        rejection: &MemPoolRejection,
+    ) {
+        let cacheable = match CacheableRejection::from_rejection(rejection) {
+            Some(cacheable) => cacheable,
+            None => return,
+        };
+        self.reset_if_new_tip(consensus_hash, block_hash);
+        if self.members.insert(txid.clone(), cacheable).is_none() {
+            self.order.push_back(txid);
+            if self.order.len() > self.max_size {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.members.remove(&oldest);
+                }
+            }
+        }
+    }
+}
+
+/// A low/middle/high estimate of the fee rate (in microSTX per unit of estimated cost) that the
+/// mempool has observed recently.  Used by miners to rank mempool candidates by how much they
+/// actually pay for the resources they consume, rather than by their absolute fee.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeeRateEstimate {
+    pub high: f64,
+    pub middle: f64,
+    pub low: f64,
+}
+
+/// The fee paid and estimated cost of a single transaction that was just processed in a block.
+/// Fed to a `FeeEstimator` so it can update its view of the going fee rate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProcessedTxFee {
+    pub fee: u64,
+    pub estimated_cost: u64,
+}
+
+/// The fee rate paid and the number of blocks that elapsed between admission and confirmation
+/// for a single transaction that was just mined.  Fed to a `FeeEstimator` so it can update its
+/// per-fee-rate-bucket confirmation-target success ratios (see
+/// `FeeEstimator::notify_confirmations`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProcessedTxConfirmation {
+    pub fee_rate: f64,
+    pub blocks_to_confirm: u64,
+}
+
+/// Tracks the fee rate (fee per unit of estimated cost) that the network has recently been
+/// willing to pay, so that the mempool walk can rank candidates by how well they pay for the
+/// resources they consume instead of by their absolute fee.
+pub trait FeeEstimator {
+    /// Update the estimator's view of the going fee rate using the transactions that were just
+    /// processed in a new block.
+    fn notify_block(&mut self, receipts: &[ProcessedTxFee]) -> Result<(), db_error>;
+    /// Return the current low/middle/high fee rate estimates, or None if no observations have
+    /// been made yet.
+    fn get_rate_estimates(&self) -> Option<FeeRateEstimate>;
+    /// Update the estimator's confirmation-target success ratios using transactions that were
+    /// just observed to confirm.  Estimators that don't bucket by confirmation target (e.g.
+    /// `NullFeeEstimator`) can rely on this default no-op.
+    fn notify_confirmations(&mut self, _confirmations: &[ProcessedTxConfirmation]) -> Result<(), db_error> {
+        Ok(())
+    }
+    /// Return the lowest fee rate, among the fee-rate buckets this estimator has observed, whose
+    /// historical success ratio at `target_blocks` meets `confidence` (e.g. 0.85 for 85%).
+    /// Returns `None` if no bucket meets the bar, or if this estimator doesn't track
+    /// confirmation targets.
+    fn estimate_fee_rate(&self, _target_blocks: u64, _confidence: f64) -> Option<f64> {
+        None
+    }
+}
+
+/// A `FeeEstimator` that never has an opinion.  Used where no fee-rate-aware ranking is wanted
+/// (e.g. in tests, or until a real estimator has been configured).
+pub struct NullFeeEstimator;
+
+impl FeeEstimator for NullFeeEstimator {
+    fn notify_block(&mut self, _receipts: &[ProcessedTxFee]) -> Result<(), db_error> {
+        Ok(())
+    }
+
+    fn get_rate_estimates(&self) -> Option<FeeRateEstimate> {
+        None
+    }
+}
+
+/// Estimate the cost of processing a transaction, for the purposes of fee-rate ranking.  This is
+/// a coarse proxy for execution cost: it's based on the transaction's serialized length, with a
+/// payload-specific multiplier applied for payloads that are known to cost more to process than
+/// their size alone would suggest.
+pub fn estimate_tx_cost(tx_len: u64, payload: &TransactionPayload) -> u64 {
+    let multiplier = match payload {
+        TransactionPayload::SmartContract(..) => 8,
+        TransactionPayload::ContractCall(..) => 4,
+        _ => 1,
+    };
+    cmp::max(1, tx_len.saturating_mul(multiplier))
+}
+
+const FEE_ESTIMATOR_SCHEMA: &'static [&'static str] = &[r#"
+    CREATE TABLE IF NOT EXISTS fee_rate_estimates(
+        id INTEGER PRIMARY KEY CHECK (id = 0),
+        low REAL NOT NULL,
+        middle REAL NOT NULL,
+        high REAL NOT NULL
+    );
+"#];
+
+// confirmation-target horizons, in blocks, that `SQLiteFeeEstimator` maintains decayed
+// confirmed/total counts for.  `estimate_fee_rate` rounds a caller's requested `target_blocks` up
+// to the nearest value here.
+const CONFIRMATION_TARGETS: &'static [u64] = &[1, 2, 3, 5, 8, 13, 20, 50, 100];
+
+// geometric spacing ratio between adjacent fee-rate buckets used by `SQLiteFeeEstimator`'s
+// confirmation tracking (see `SQLiteFeeEstimator::fee_rate_bucket`).  Mirrors the bucketing
+// scheme Bitcoin Core's `TxConfirmStats` uses for the same purpose.
+const FEE_RATE_BUCKET_RATIO: f64 = 1.1;
+
+const FEE_ESTIMATOR_CONFIRMATIONS_SCHEMA: &'static [&'static str] = &[r#"
+    CREATE TABLE IF NOT EXISTS fee_rate_confirmations(
+        bucket INTEGER NOT NULL,
+        target_blocks INTEGER NOT NULL,
+        confirmed REAL NOT NULL DEFAULT 0,
+        total REAL NOT NULL DEFAULT 0,
+        PRIMARY KEY(bucket, target_blocks)
+    );
+"#];
+
+/// A `FeeEstimator` that persists an exponentially-weighted moving average of the fee rate
+/// observed in each processed block to a SQLite database, along with, per geometrically-spaced
+/// fee-rate bucket and confirmation-target horizon, a decayed (confirmed-within-target, total)
+/// pair built from individual transactions' admission-to-confirmation delays (see
+/// `notify_confirmations`).
+pub struct SQLiteFeeEstimator {
+    db: DBConn,
+    /// weight given to each new observation, in [0, 1]
+    decay_rate: f64,
+}
+
+impl SQLiteFeeEstimator {
+    pub fn open(path: &str, decay_rate: f64) -> Result<SQLiteFeeEstimator, db_error> {
+        let mut create_flag = false;
+        let open_flags = if fs::metadata(path).is_err() {
+            create_flag = true;
+            OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE
+        } else {
+            OpenFlags::SQLITE_OPEN_READ_WRITE
+        };
+
+        let conn = DBConn::open_with_flags(path, open_flags).map_err(db_error::SqliteError)?;
+        if create_flag {
+            for cmd in FEE_ESTIMATOR_SCHEMA {
+                conn.execute_batch(cmd).map_err(db_error::SqliteError)?;
+            }
+            for cmd in FEE_ESTIMATOR_CONFIRMATIONS_SCHEMA {
+                conn.execute_batch(cmd).map_err(db_error::SqliteError)?;
+            }
+        }
+
+        Ok(SQLiteFeeEstimator { db: conn, decay_rate })
+    }
+
+    /// Which geometrically-spaced bucket a fee rate falls into.  Buckets are indexed so that
+    /// `bucket_fee_rate(fee_rate_bucket(r))` is the lower edge of the bucket containing `r`.
+    fn fee_rate_bucket(fee_rate: f64) -> i64 {
+        if fee_rate <= 0.0 {
+            return i64::min_value();
+        }
+        (fee_rate.ln() / FEE_RATE_BUCKET_RATIO.ln()).floor() as i64
+    }
+
+    /// The representative fee rate (lower edge) of a bucket index, inverting `fee_rate_bucket`.
+    fn bucket_fee_rate(bucket: i64) -> f64 {
+        FEE_RATE_BUCKET_RATIO.powi(bucket as i32)
+    }
+
+    /// The smallest value in `CONFIRMATION_TARGETS` that is at least `target_blocks`, or the
+    /// largest tracked target if `target_blocks` exceeds all of them.
+    fn nearest_target(target_blocks: u64) -> u64 {
+        CONFIRMATION_TARGETS
+            .iter()
+            .copied()
+            .find(|t| *t >= target_blocks)
+            .unwrap_or_else(|| *CONFIRMATION_TARGETS.last().expect("CONFIRMATION_TARGETS is non-empty"))
+    }
+
+    fn get_confirmation_counts(&self, bucket: i64, target_blocks: u64) -> Option<(f64, f64)> {
+        self.db
+            .query_row(
+                "SELECT confirmed, total FROM fee_rate_confirmations WHERE bucket = ?1 AND target_blocks = ?2",
+                &[&bucket as &dyn ToSql, &(target_blocks as i64)],
+                |row| Ok((row.get_unwrap(0), row.get_unwrap(1))),
+            )
+            .optional()
+            .ok()
+            .flatten()
+    }
+}
+
+impl FeeEstimator for SQLiteFeeEstimator {
+    fn notify_block(&mut self, receipts: &[ProcessedTxFee]) -> Result<(), db_error> {
+        let (fee_sum, cost_sum) = receipts.iter().fold((0u64, 0u64), |(fees, costs), r| {
+            (
+                fees.saturating_add(r.fee),
+                costs.saturating_add(cmp::max(1, r.estimated_cost)),
+            )
+        });
+        if cost_sum == 0 {
+            return Ok(());
+        }
+
+        let observed_rate = fee_sum as f64 / cost_sum as f64;
+        let next = match self.get_rate_estimates() {
+            Some(prior) => FeeRateEstimate {
+                low: prior.low * (1.0 - self.decay_rate) + (observed_rate * 0.5) * self.decay_rate,
+                middle: prior.middle * (1.0 - self.decay_rate) + observed_rate * self.decay_rate,
+                high: prior.high * (1.0 - self.decay_rate)
+                    + (observed_rate * 1.5) * self.decay_rate,
+            },
+            None => FeeRateEstimate {
+                low: observed_rate * 0.5,
+                middle: observed_rate,
+                high: observed_rate * 1.5,
+            },
+        };
+
+        self.db
+            .execute(
+                "INSERT OR REPLACE INTO fee_rate_estimates (id, low, middle, high) VALUES (0, ?1, ?2, ?3)",
+                &[&next.low as &dyn ToSql, &next.middle, &next.high],
+            )
+            .map_err(db_error::SqliteError)?;
+        Ok(())
+    }
+
+    fn get_rate_estimates(&self) -> Option<FeeRateEstimate> {
+        self.db
+            .query_row(
+                "SELECT low, middle, high FROM fee_rate_estimates WHERE id = 0",
+                NO_PARAMS,
+                |row| {
+                    Ok(FeeRateEstimate {
+                        low: row.get_unwrap(0),
+                        middle: row.get_unwrap(1),
+                        high: row.get_unwrap(2),
+                    })
+                },
+            )
+            .optional()
+            .ok()
+            .flatten()
+    }
+
+    fn notify_confirmations(&mut self, confirmations: &[ProcessedTxConfirmation]) -> Result<(), db_error> {
+        for confirmation in confirmations.iter() {
+            let bucket = SQLiteFeeEstimator::fee_rate_bucket(confirmation.fee_rate);
+            for target in CONFIRMATION_TARGETS.iter() {
+                let (prior_confirmed, prior_total) = self
+                    .get_confirmation_counts(bucket, *target)
+                    .unwrap_or((0.0, 0.0));
+
+                let confirmed_this_round = if confirmation.blocks_to_confirm <= *target {
+                    1.0
+                } else {
+                    0.0
+                };
+
+                let next_confirmed = prior_confirmed * (1.0 - self.decay_rate)
+                    + confirmed_this_round * self.decay_rate;
+                let next_total = prior_total * (1.0 - self.decay_rate) + self.decay_rate;
+
+                self.db
+                    .execute(
+                        "INSERT OR REPLACE INTO fee_rate_confirmations (bucket, target_blocks, confirmed, total) VALUES (?1, ?2, ?3, ?4)",
+                        &[
+                            &bucket as &dyn ToSql,
+                            &(*target as i64),
+                            &next_confirmed,
+                            &next_total,
+                        ],
+                    )
+                    .map_err(db_error::SqliteError)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn estimate_fee_rate(&self, target_blocks: u64, confidence: f64) -> Option<f64> {
+        let target = SQLiteFeeEstimator::nearest_target(target_blocks);
+
+        let mut stmt = self
+            .db
+            .prepare(
+                "SELECT bucket, confirmed, total FROM fee_rate_confirmations \
+                 WHERE target_blocks = ?1 ORDER BY bucket DESC",
+            )
+            .ok()?;
+        let rows = stmt
+            .query_map(&[&(target as i64) as &dyn ToSql], |row| {
+                let bucket: i64 = row.get_unwrap(0);
+                let confirmed: f64 = row.get_unwrap(1);
+                let total: f64 = row.get_unwrap(2);
+                Ok((bucket, confirmed, total))
+            })
+            .ok()?;
+
+        // scan from the highest fee-rate bucket downward, remembering the lowest bucket seen so
+        // far that still meets `confidence` -- since higher fee rates confirm at least as
+        // reliably as lower ones, the first bucket that falls short marks the floor.
+        let mut lowest_meeting_confidence = None;
+        for row in rows {
+            let (bucket, confirmed, total) = row.ok()?;
+            if total <= 0.0 {
+                continue;
+            }
+            if confirmed / total >= confidence {
+                lowest_meeting_confidence = Some(SQLiteFeeEstimator::bucket_fee_rate(bucket));
+            } else {
+                break;
+            }
+        }
+
+        lowest_meeting_confidence
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct MemPoolTxInfo {
     pub tx: StacksTransaction,
@@ -191,9 +760,15 @@ pub struct MemPoolTxMetadata {
     pub txid: Txid,
     pub len: u64,
     pub tx_fee: u64,
+    /// coarse estimate of the cost of processing this transaction, used to rank mempool
+    /// candidates by fee rate instead of by absolute fee (see `estimate_tx_cost`)
+    pub estimated_cost: u64,
     pub consensus_hash: ConsensusHash,
     pub block_header_hash: BlockHeaderHash,
     pub block_height: u64,
+    /// monotonic, fork-agnostic tenure height at admission time (see
+    /// `MemPoolDB::clear_before_coinbase_height`)
+    pub coinbase_height: u64,
     pub origin_address: StacksAddress,
     pub origin_nonce: u64,
     pub sponsor_address: StacksAddress,
@@ -201,6 +776,13 @@ pub struct MemPoolTxMetadata {
     pub accept_time: u64,
 }
 
+impl MemPoolTxMetadata {
+    /// The fee rate (fee per unit of estimated cost) this transaction pays.
+    pub fn fee_rate(&self) -> f64 {
+        self.tx_fee as f64 / cmp::max(1, self.estimated_cost) as f64
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MemPoolWalkSettings {
     /// Minimum transaction fee that will be considered
@@ -210,6 +792,17 @@ pub struct MemPoolWalkSettings {
     pub max_walk_time_ms: u64,
 }
 
+/// Why did `MemPoolDB::iterate_candidates` stop iterating?
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MempoolIterationStopReason {
+    /// There are no more origin addresses to consider in the mempool.
+    NoMoreCandidates,
+    /// The walk's soft deadline was reached before the mempool was exhausted.
+    DeadlineReached,
+    /// The `todo` closure asked the walk to stop early.
+    IteratorExited,
+}
+
 impl MemPoolWalkSettings {
     pub fn default() -> MemPoolWalkSettings {
         MemPoolWalkSettings {
@@ -238,7 +831,9 @@ impl FromRow<MemPoolTxMetadata> for MemPoolTxMetadata {
         let block_header_hash = BlockHeaderHash::from_column(row, "block_header_hash")?;
         let tx_fee = u64::from_column(row, "tx_fee")?;
         let height = u64::from_column(row, "height")?;
+        let coinbase_height = u64::from_column(row, "coinbase_height")?;
         let len = u64::from_column(row, "length")?;
+        let estimated_cost = u64::from_column(row, "estimated_cost")?;
         let ts = u64::from_column(row, "accept_time")?;
         let origin_address = StacksAddress::from_column(row, "origin_address")?;
         let origin_nonce = u64::from_column(row, "origin_nonce")?;
@@ -249,9 +844,11 @@ impl FromRow<MemPoolTxMetadata> for MemPoolTxMetadata {
             txid: txid,
             tx_fee: tx_fee,
             len: len,
+            estimated_cost: estimated_cost,
             consensus_hash: consensus_hash,
             block_header_hash: block_header_hash,
             block_height: height,
+            coinbase_height: coinbase_height,
             accept_time: ts,
             origin_address: origin_address,
             origin_nonce: origin_nonce,
@@ -279,6 +876,72 @@ impl FromRow<MemPoolTxInfo> for MemPoolTxInfo {
     }
 }
 
+/// A transaction buffered in `pending_mempool`, awaiting promotion into `mempool` once its
+/// origin nonce becomes reachable (see `MemPoolDB::promote_pending_tx`).
+struct PendingMemPoolTx {
+    txid: Txid,
+    tx_bytes: Vec<u8>,
+    tx_fee: u64,
+    sponsor_address: StacksAddress,
+    sponsor_nonce: u64,
+    consensus_hash: ConsensusHash,
+    block_header_hash: BlockHeaderHash,
+}
+
+impl FromRow<PendingMemPoolTx> for PendingMemPoolTx {
+    fn from_row<'a>(row: &'a Row) -> Result<PendingMemPoolTx, db_error> {
+        let txid = Txid::from_column(row, "txid")?;
+        let tx_bytes: Vec<u8> = row.get_unwrap("tx");
+        let tx_fee = u64::from_column(row, "tx_fee")?;
+        let sponsor_address = StacksAddress::from_column(row, "sponsor_address")?;
+        let sponsor_nonce = u64::from_column(row, "sponsor_nonce")?;
+        let consensus_hash = ConsensusHash::from_column(row, "consensus_hash")?;
+        let block_header_hash = BlockHeaderHash::from_column(row, "block_header_hash")?;
+
+        Ok(PendingMemPoolTx {
+            txid,
+            tx_bytes,
+            tx_fee,
+            sponsor_address,
+            sponsor_nonce,
+            consensus_hash,
+            block_header_hash,
+        })
+    }
+}
+
+/// The fields of a `mempool` row needed to rank it by fee rate when picking eviction
+/// candidates in `MemPoolDB::evict_txs_for_space`.
+struct MempoolFeeRateEntry {
+    txid: Txid,
+    tx_fee: u64,
+    length: u64,
+}
+
+impl FromRow<MempoolFeeRateEntry> for MempoolFeeRateEntry {
+    fn from_row<'a>(row: &'a Row) -> Result<MempoolFeeRateEntry, db_error> {
+        let txid = Txid::from_column(row, "txid")?;
+        let tx_fee = u64::from_column(row, "tx_fee")?;
+        let length = u64::from_column(row, "length")?;
+        Ok(MempoolFeeRateEntry {
+            txid,
+            tx_fee,
+            length,
+        })
+    }
+}
+
+impl MempoolFeeRateEntry {
+    /// Compare fee rates (`tx_fee / length`) as a rational number via cross-multiplication
+    /// (`self.tx_fee * other.length` vs `other.tx_fee * self.length`), to avoid the
+    /// floating-point drift that `tx_fee as f64 / length as f64` would introduce.
+    fn cmp_fee_rate(&self, other: &MempoolFeeRateEntry) -> cmp::Ordering {
+        let lhs = (self.tx_fee as u128) * (other.length as u128);
+        let rhs = (other.tx_fee as u128) * (self.length as u128);
+        lhs.cmp(&rhs)
+    }
+}
+
 impl FromRow<(u64, u64)> for (u64, u64) {
     fn from_row<'a>(row: &'a Row) -> Result<(u64, u64), db_error> {
         let t1: i64 = row.get_unwrap(0);
@@ -300,11 +963,14 @@ const MEMPOOL_INITIAL_SCHEMA: &'static [&'static str] = &[
         sponsor_nonce INTEGER NOT NULL,
         tx_fee INTEGER NOT NULL,
         length INTEGER NOT NULL,
+        estimated_cost INTEGER NOT NULL,    -- coarse proxy for the cost of processing this tx
         consensus_hash TEXT NOT NULL,
         block_header_hash TEXT NOT NULL,
         height INTEGER NOT NULL,    -- stacks block height
         accept_time INTEGER NOT NULL,
         tx BLOB NOT NULL,
+        reintroduced INTEGER NOT NULL DEFAULT 0,    -- see `MemPoolDB::reinject_orphaned_block`
+        coinbase_height INTEGER NOT NULL DEFAULT 0,    -- fork-agnostic tenure height; see `MemPoolDB::clear_before_coinbase_height`
         PRIMARY KEY (txid),
         UNIQUE (origin_address, origin_nonce),
         UNIQUE (sponsor_address,sponsor_nonce)
@@ -316,6 +982,27 @@ const MEMPOOL_INITIAL_SCHEMA: &'static [&'static str] = &[
     "CREATE INDEX by_origin ON mempool(origin_address, origin_nonce);",
     "CREATE INDEX by_timestamp ON mempool(accept_time);",
     "CREATE INDEX by_chaintip ON mempool(consensus_hash,block_header_hash);",
+    "CREATE INDEX by_fee_rate ON mempool(estimated_cost, tx_fee);",
+    "CREATE INDEX by_coinbase_height ON mempool(coinbase_height);",
+];
+
+// migration for mempools predating the `coinbase_height` column (a monotonic, fork-agnostic
+// tenure height recorded alongside the per-fork `height`).  Sync and GC key off of it instead of
+// `height` so they don't double-count transactions that land at the same block height on
+// competing forks, and don't need to re-key themselves across a sortition-level reorg that only
+// rewinds `height`.  Guarded the same way as `MEMPOOL_SCHEMA_REINTRODUCED` below.
+const MEMPOOL_SCHEMA_COINBASE_HEIGHT: &'static [&'static str] = &[
+    "ALTER TABLE mempool ADD COLUMN coinbase_height INTEGER NOT NULL DEFAULT 0;",
+    "CREATE INDEX IF NOT EXISTS by_coinbase_height ON mempool(coinbase_height);",
+];
+
+// migration for mempools predating the `reintroduced` column (used to flag txs that
+// `reinject_orphaned_block` pulled back in from a losing fork, so `iterate_candidates` can
+// prefer them over freshly-arrived txs of the same fee rate).  `ALTER TABLE ... ADD COLUMN`
+// isn't idempotent the way `CREATE TABLE IF NOT EXISTS` is, so `MemPoolDB::open` only runs this
+// after checking the column isn't already there.
+const MEMPOOL_SCHEMA_REINTRODUCED: &'static [&'static str] = &[
+    "ALTER TABLE mempool ADD COLUMN reintroduced INTEGER NOT NULL DEFAULT 0;",
 ];
 
 const MEMPOOL_SCHEMA_BLOOM_STATE: &'static [&'static str] = &[
@@ -340,18 +1027,78 @@ const MEMPOOL_SCHEMA_BLOOM_STATE: &'static [&'static str] = &[
     "#,
 ];
 
+// unlike `removed_txids`, this table is *not* foreign-keyed to `mempool` -- a txid that's
+// dropped for being invalid (e.g. TOO_EXPENSIVE) must stay blacklisted even after its mempool
+// row (and any FK-cascaded bookkeeping) is gone, so it can't simply be resubmitted and re-walked
+// by a miner.
+const MEMPOOL_SCHEMA_BLACKLISTED_TXIDS: &'static [&'static str] = &[
+    r#"
+    CREATE TABLE IF NOT EXISTS blacklisted_txids(
+        txid TEXT PRIMARY KEY NOT NULL,
+        reason TEXT NOT NULL,
+        blacklist_time INTEGER NOT NULL
+    );
+    "#,
+    "CREATE INDEX IF NOT EXISTS by_blacklist_time ON blacklisted_txids(blacklist_time);",
+];
+
+// holds transactions deferred by strict sequential admission (see
+// `MemPoolAdmitter::set_strict_sequential_admission`) because their origin nonce is ahead of
+// the account's confirmed nonce.  A deferred tx is promoted into `mempool` -- and so becomes
+// eligible for `iterate_candidates` -- once the nonces before it have been filled in.
+const MEMPOOL_SCHEMA_PENDING_TXS: &'static [&'static str] = &[
+    r#"
+    CREATE TABLE IF NOT EXISTS pending_mempool(
+        txid TEXT NOT NULL,
+        origin_address TEXT NOT NULL,
+        origin_nonce INTEGER NOT NULL,
+        sponsor_address TEXT NOT NULL,
+        sponsor_nonce INTEGER NOT NULL,
+        tx_fee INTEGER NOT NULL,
+        length INTEGER NOT NULL,
+        consensus_hash TEXT NOT NULL,
+        block_header_hash TEXT NOT NULL,
+        accept_time INTEGER NOT NULL,
+        tx BLOB NOT NULL,
+        PRIMARY KEY (txid),
+        UNIQUE (origin_address, origin_nonce)
+    );
+    "#,
+    "CREATE INDEX IF NOT EXISTS by_pending_origin ON pending_mempool(origin_address, origin_nonce);",
+];
+
+// a single-row cache of `SUM(length)` over `mempool`, maintained incrementally by
+// `MemPoolDB::adjust_mempool_bytes` so `try_add_tx` doesn't have to re-scan the whole table on
+// every insert just to decide whether it needs to evict (see `MemPoolDB::evict_txs_for_space`).
+const MEMPOOL_SCHEMA_STATS: &'static [&'static str] = &[
+    r#"
+    CREATE TABLE IF NOT EXISTS mempool_stats(
+        total_bytes INTEGER NOT NULL
+    );
+    "#,
+];
+
 pub struct MemPoolDB {
     db: DBConn,
     path: String,
     admitter: MemPoolAdmitter,
+    // `BloomNodeHasher` computes this counter's k hash positions per insert/query, which is
+    // why `make_mempool_sync_data` is dominated by hashing cost once the mempool is large
+    // (see the per-call timings in `test_make_mempool_sync_data`).  `util::bloom` should move
+    // `BloomNodeHasher` to Kirsch-Mitzenmacher double-hashing -- two base hashes combined into
+    // k positions, same false-positive rate for a fraction of the hashing work -- the next time
+    // that module is touched.
     bloom_counter: BloomCounter<BloomNodeHasher>,
     max_tx_tags: u32,
+    max_inventory_txs: u32,
+    recent_rejects: RecentRejects,
 }
 
 pub struct MemPoolTx<'a> {
     tx: DBTx<'a>,
     admitter: &'a mut MemPoolAdmitter,
     bloom_counter: Option<&'a mut BloomCounter<BloomNodeHasher>>,
+    recent_rejects: &'a mut RecentRejects,
 }
 
 impl<'a> Deref for MemPoolTx<'a> {
@@ -372,11 +1119,13 @@ impl<'a> MemPoolTx<'a> {
         tx: DBTx<'a>,
         admitter: &'a mut MemPoolAdmitter,
         bloom_counter: &'a mut BloomCounter<BloomNodeHasher>,
+        recent_rejects: &'a mut RecentRejects,
     ) -> MemPoolTx<'a> {
         MemPoolTx {
             tx,
             admitter,
             bloom_counter: Some(bloom_counter),
+            recent_rejects,
         }
     }
 
@@ -418,13 +1167,19 @@ impl MemPoolTxInfo {
                 (origin_address.clone(), origin_nonce)
             };
 
+        let len = tx_data.len() as u64;
         let metadata = MemPoolTxMetadata {
             txid: txid,
-            len: tx_data.len() as u64,
+            len: len,
             tx_fee: tx.get_tx_fee(),
+            estimated_cost: estimate_tx_cost(len, &tx.payload),
             consensus_hash: consensus_hash,
             block_header_hash: block_header_hash,
             block_height: block_height,
+            // `from_tx` isn't wired up to chainstate, so it has no way to look up the real
+            // coinbase height -- fall back to the per-fork block height, same as `try_add_tx`
+            // does when a tip's coinbase height can't be determined.
+            coinbase_height: block_height,
             origin_address: origin_address,
             origin_nonce: origin_nonce,
             sponsor_address: sponsor_address,
@@ -450,6 +1205,21 @@ impl MemPoolDB {
         }
 
         MemPoolDB::instantiate_bloom_state(&mut tx)?;
+
+        for cmd in MEMPOOL_SCHEMA_BLACKLISTED_TXIDS {
+            tx.execute_batch(cmd).map_err(db_error::SqliteError)?;
+        }
+
+        for cmd in MEMPOOL_SCHEMA_PENDING_TXS {
+            tx.execute_batch(cmd).map_err(db_error::SqliteError)?;
+        }
+
+        for cmd in MEMPOOL_SCHEMA_STATS {
+            tx.execute_batch(cmd).map_err(db_error::SqliteError)?;
+        }
+        tx.execute("INSERT INTO mempool_stats (total_bytes) VALUES (0)", NO_PARAMS)
+            .map_err(db_error::SqliteError)?;
+
         tx.commit().map_err(db_error::SqliteError)?;
         Ok(())
     }
@@ -479,6 +1249,23 @@ impl MemPoolDB {
             .map(String::from)
     }
 
+    /// Does `mempool` already have a column named `column`?  Used to guard a migration
+    /// (`ALTER TABLE ... ADD COLUMN`) that, unlike the `CREATE TABLE IF NOT EXISTS` migrations
+    /// above, isn't already idempotent on its own.
+    fn mempool_column_exists(conn: &DBConn, column: &str) -> Result<bool, db_error> {
+        let mut stmt = conn
+            .prepare("PRAGMA table_info(mempool)")
+            .map_err(db_error::SqliteError)?;
+        let mut rows = stmt.query(NO_PARAMS).map_err(db_error::SqliteError)?;
+        while let Some(row) = rows.next().map_err(db_error::SqliteError)? {
+            let name: String = row.get_unwrap(1);
+            if name == column {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
     /// Open the mempool db within the chainstate directory.
     /// The chainstate must be instantiated already.
     pub fn open(
@@ -530,6 +1317,59 @@ impl MemPoolDB {
                 MemPoolDB::instantiate_bloom_state(&mut tx)?;
                 tx.commit().map_err(db_error::SqliteError)?;
             }
+
+            // possibly migrating from a mempool without the blacklist table.  `CREATE TABLE IF
+            // NOT EXISTS` makes this a no-op on a mempool that already has it.
+            let mut tx = tx_begin_immediate(&mut conn)?;
+            for cmd in MEMPOOL_SCHEMA_BLACKLISTED_TXIDS {
+                tx.execute_batch(cmd).map_err(db_error::SqliteError)?;
+            }
+            tx.commit().map_err(db_error::SqliteError)?;
+
+            // possibly migrating from a mempool without the pending-tx holding area.
+            let mut tx = tx_begin_immediate(&mut conn)?;
+            for cmd in MEMPOOL_SCHEMA_PENDING_TXS {
+                tx.execute_batch(cmd).map_err(db_error::SqliteError)?;
+            }
+            tx.commit().map_err(db_error::SqliteError)?;
+
+            // possibly migrating from a mempool without the cached byte-total used for
+            // eviction.  Backfill it from the existing rows this one time, rather than paying
+            // for a `SUM(length)` scan on every `try_add_tx` going forward.
+            let mut tx = tx_begin_immediate(&mut conn)?;
+            for cmd in MEMPOOL_SCHEMA_STATS {
+                tx.execute_batch(cmd).map_err(db_error::SqliteError)?;
+            }
+            let has_stats_row: Option<i64> =
+                query_row(&tx, "SELECT total_bytes FROM mempool_stats", NO_PARAMS)?;
+            if has_stats_row.is_none() {
+                let existing_bytes =
+                    query_int(&tx, "SELECT COALESCE(SUM(length), 0) FROM mempool", NO_PARAMS)?;
+                tx.execute(
+                    "INSERT INTO mempool_stats (total_bytes) VALUES (?1)",
+                    &[&existing_bytes],
+                )
+                .map_err(db_error::SqliteError)?;
+            }
+            tx.commit().map_err(db_error::SqliteError)?;
+
+            // possibly migrating from a mempool without the `reintroduced` column.
+            if !MemPoolDB::mempool_column_exists(&conn, "reintroduced")? {
+                let mut tx = tx_begin_immediate(&mut conn)?;
+                for cmd in MEMPOOL_SCHEMA_REINTRODUCED {
+                    tx.execute_batch(cmd).map_err(db_error::SqliteError)?;
+                }
+                tx.commit().map_err(db_error::SqliteError)?;
+            }
+
+            // possibly migrating from a mempool without the `coinbase_height` column.
+            if !MemPoolDB::mempool_column_exists(&conn, "coinbase_height")? {
+                let mut tx = tx_begin_immediate(&mut conn)?;
+                for cmd in MEMPOOL_SCHEMA_COINBASE_HEIGHT {
+                    tx.execute_batch(cmd).map_err(db_error::SqliteError)?;
+                }
+                tx.commit().map_err(db_error::SqliteError)?;
+            }
         }
 
         let bloom_counter = BloomCounter::<BloomNodeHasher>::try_load(&conn, BLOOM_COUNTER_TABLE)?
@@ -541,10 +1381,14 @@ impl MemPoolDB {
             admitter: admitter,
             bloom_counter,
             max_tx_tags: DEFAULT_MAX_TX_TAGS,
+            max_inventory_txs: DEFAULT_MAX_INVENTORY_TXS,
+            recent_rejects: RecentRejects::new(DEFAULT_MAX_RECENT_REJECTS),
         })
     }
 
-    /// Find the origin addresses who have sent the highest-fee transactions
+    /// Find the origin addresses who have sent the highest-fee-rate transactions, i.e. the
+    /// transactions that pay the most per unit of estimated cost rather than the most in
+    /// absolute terms.
     fn find_origin_addresses_by_descending_fees(
         &self,
         start_height: i64,
@@ -553,7 +1397,10 @@ impl MemPoolDB {
         offset: u32,
         count: u32,
     ) -> Result<Vec<StacksAddress>, db_error> {
-        let sql = "SELECT DISTINCT origin_address FROM mempool WHERE height > ?1 AND height <= ?2 AND tx_fee >= ?3 ORDER BY tx_fee DESC LIMIT ?4 OFFSET ?5";
+        // `MAX(reintroduced)` breaks ties between origins paying the same fee rate in favor of
+        // one with a tx `reinject_orphaned_block` pulled back in from a losing fork, so it's
+        // re-mined ahead of a freshly-arrived competitor rather than on arbitrary row order.
+        let sql = "SELECT origin_address FROM mempool WHERE height > ?1 AND height <= ?2 AND tx_fee >= ?3 GROUP BY origin_address ORDER BY MAX(CAST(tx_fee AS REAL) / estimated_cost) DESC, MAX(reintroduced) DESC LIMIT ?4 OFFSET ?5";
         let args: &[&dyn ToSql] = &[
             &start_height,
             &end_height,
@@ -567,22 +1414,32 @@ impl MemPoolDB {
     ///
     /// Iterate over candidates in the mempool
     ///  `todo` will be called once for each transaction whose origin nonce is equal
-    ///  to the origin account's nonce. At most one transaction per origin will be
-    ///  considered by this method, and transactions will be considered in
-    ///  highest-fee-first order.  This method is interruptable -- in the `settings` struct, the
-    ///  caller may choose how long to spend iterating before this method stops.
+    ///  to the origin account's nonce. Origins are visited in highest-fee-rate-first order
+    ///  (fee per unit of `fee_estimator`-estimated cost), rather than by absolute fee; once an
+    ///  origin's head transaction is accepted, its chain is walked contiguously -- offering
+    ///  nonce N+1, N+2, ... from the same origin -- for up to `MAXIMUM_MEMPOOL_TX_CHAINING`
+    ///  transactions or until the chain runs out, before moving on to the next origin's head.
+    ///  This method is interruptable -- in the `settings` struct, the caller may choose how long
+    ///  to spend iterating before this method stops.
     ///
-    ///  Returns the number of transactions considered on success.
+    ///  `todo` is additionally passed the estimated fee rate of each candidate, so that callers
+    ///  can impose their own cutoff.
+    ///
+    ///  Returns the number of transactions considered, along with the reason why iteration
+    ///  stopped, on success.  Callers can use the stop reason to decide whether or not it's
+    ///  worth looping again (e.g. if the mempool was exhausted, looping again is pointless, but
+    ///  if we merely hit our deadline, there may be more fee-paying work available).
     pub fn iterate_candidates<F, E, C>(
         &self,
         clarity_tx: &mut C,
         tip_height: u64,
         settings: MemPoolWalkSettings,
+        fee_estimator: &dyn FeeEstimator,
         mut todo: F,
-    ) -> Result<u64, E>
+    ) -> Result<(u64, MempoolIterationStopReason), E>
     where
         C: ClarityConnection,
-        F: FnMut(&mut C, MemPoolTxInfo) -> Result<bool, E>,
+        F: FnMut(&mut C, MemPoolTxInfo, f64) -> Result<bool, E>,
         E: From<db_error> + From<ChainstateError>,
     {
         let min_height = (tip_height as i64)
@@ -600,14 +1457,24 @@ impl MemPoolDB {
         let mut total_origins = 0;
 
         test_debug!(
-            "Mempool walk for {}ms, min tx fee {}",
+            "Mempool walk for {}ms, min tx fee {}, fee rate estimate {:?}",
             settings.max_walk_time_ms,
             min_tx_fee,
+            fee_estimator.get_rate_estimates(),
         );
 
-        loop {
+        let mut stop_reason = MempoolIterationStopReason::NoMoreCandidates;
+
+        // Tracks the next nonce we expect to offer for each origin we've already visited, so
+        // that a chain begun on one pass over `origin_addresses` picks up where it left off
+        // instead of re-offering the same head transaction (or re-querying chainstate for an
+        // account nonce we've already accounted for).
+        let mut next_nonce_by_origin: HashMap<StacksAddress, u64> = HashMap::new();
+
+        'outer: loop {
             if deadline <= get_epoch_time_ms() {
                 debug!("Mempool iteration deadline exceeded");
+                stop_reason = MempoolIterationStopReason::DeadlineReached;
                 break;
             }
 
@@ -628,64 +1495,87 @@ impl MemPoolDB {
 
             if origin_addresses.len() == 0 {
                 debug!("No more origin addresses to consider");
+                stop_reason = MempoolIterationStopReason::NoMoreCandidates;
                 break;
             }
 
             for origin_address in origin_addresses.iter() {
                 if deadline <= get_epoch_time_ms() {
                     debug!("Mempool iteration deadline exceeded");
-                    break;
+                    stop_reason = MempoolIterationStopReason::DeadlineReached;
+                    break 'outer;
                 }
 
-                let min_origin_nonce = StacksChainState::get_account(
-                    clarity_tx,
-                    &PrincipalData::Standard(origin_address.to_owned().into()),
-                )
-                .nonce;
+                let mut origin_nonce = match next_nonce_by_origin.get(origin_address) {
+                    Some(nonce) => *nonce,
+                    None => {
+                        StacksChainState::get_account(
+                            clarity_tx,
+                            &PrincipalData::Standard(origin_address.to_owned().into()),
+                        )
+                        .nonce
+                    }
+                };
 
                 total_origins += 1;
 
                 debug!(
                     "Consider mempool transactions from origin address {} nonce {}",
-                    &origin_address, min_origin_nonce
+                    &origin_address, origin_nonce
                 );
 
-                let sql = "SELECT * FROM mempool WHERE origin_address = ?1 AND height > ?2 AND height <= ?3 AND origin_nonce = ?4 AND tx_fee >= ?5 ORDER BY sponsor_nonce ASC LIMIT 1";
-                let args: &[&dyn ToSql] = &[
-                    &origin_address.to_string(),
-                    &min_height,
-                    &max_height,
-                    &u64_to_sql(min_origin_nonce)?,
-                    &u64_to_sql(min_tx_fee)?,
-                ];
+                for _ in 0..MAXIMUM_MEMPOOL_TX_CHAINING {
+                    // `reintroduced` breaks ties between transactions of the same fee rate in
+                    // favor of one `reinject_orphaned_block` pulled back in from a losing fork,
+                    // so it gets re-mined promptly instead of competing from the back of the
+                    // queue against freshly-arrived txs.
+                    let sql = "SELECT * FROM mempool WHERE origin_address = ?1 AND height > ?2 AND height <= ?3 AND origin_nonce = ?4 AND tx_fee >= ?5 AND NOT EXISTS (SELECT 1 FROM blacklisted_txids WHERE txid = mempool.txid) ORDER BY (CAST(tx_fee AS REAL) / estimated_cost) DESC, reintroduced DESC, sponsor_nonce ASC LIMIT 1";
+                    let args: &[&dyn ToSql] = &[
+                        &origin_address.to_string(),
+                        &min_height,
+                        &max_height,
+                        &u64_to_sql(origin_nonce)?,
+                        &u64_to_sql(min_tx_fee)?,
+                    ];
+
+                    let tx_opt = query_row::<MemPoolTxInfo, _>(self.conn(), sql, args)?;
+                    let tx = match tx_opt {
+                        Some(tx) => tx,
+                        None => break,
+                    };
 
-                let tx_opt = query_row::<MemPoolTxInfo, _>(self.conn(), sql, args)?;
-                if let Some(tx) = tx_opt {
                     total_considered += 1;
+                    let fee_rate = tx.metadata.fee_rate();
                     debug!(
-                        "Consider transaction {} from {} between heights {},{} with nonce = {} and tx_fee = {} and size = {}",
+                        "Consider transaction {} from {} between heights {},{} with nonce = {} and tx_fee = {} and size = {} and fee rate = {}",
                         &tx.metadata.txid,
                         &origin_address,
                         min_height,
                         max_height,
-                        min_origin_nonce,
+                        origin_nonce,
                         tx.metadata.tx_fee,
-                        tx.metadata.len
+                        tx.metadata.len,
+                        fee_rate
                     );
 
-                    if !todo(clarity_tx, tx)? {
+                    let keep_going = todo(clarity_tx, tx, fee_rate)?;
+                    origin_nonce += 1;
+                    next_nonce_by_origin.insert(origin_address.clone(), origin_nonce);
+
+                    if !keep_going {
                         test_debug!("Mempool early return from iteration");
-                        break;
+                        stop_reason = MempoolIterationStopReason::IteratorExited;
+                        break 'outer;
                     }
                 }
             }
             offset += 1;
         }
         debug!(
-            "Mempool iteration finished; considered {} transactions across {} origin addresses",
-            total_considered, total_origins
+            "Mempool iteration finished; considered {} transactions across {} origin addresses, stopped because {:?}",
+            total_considered, total_origins, &stop_reason
         );
-        Ok(total_considered)
+        Ok((total_considered, stop_reason))
     }
 
     pub fn conn(&self) -> &DBConn {
@@ -698,9 +1588,17 @@ impl MemPoolDB {
             tx,
             &mut self.admitter,
             &mut self.bloom_counter,
+            &mut self.recent_rejects,
         ))
     }
 
+    /// Configure how many txids `RecentRejects` remembers at once.  Lowering this after the
+    /// cache already holds more entries than the new size takes effect gradually, as entries are
+    /// evicted one at a time on subsequent rejections.
+    pub fn set_max_recent_rejects(&mut self, max_size: usize) {
+        self.recent_rejects.max_size = max_size;
+    }
+
     fn db_has_tx(conn: &DBConn, txid: &Txid) -> Result<bool, db_error> {
         query_row(
             conn,
@@ -718,6 +1616,100 @@ impl MemPoolDB {
         )
     }
 
+    /// The running total size (in bytes) of all transactions currently held in `mempool`, as
+    /// cached in `mempool_stats` (see `adjust_mempool_bytes`).
+    fn get_mempool_bytes(conn: &DBConn) -> Result<u64, db_error> {
+        let total_bytes = query_int(conn, "SELECT total_bytes FROM mempool_stats", NO_PARAMS)?;
+        Ok(total_bytes as u64)
+    }
+
+    /// Apply `delta` to the cached running byte-total in `mempool_stats`.  `delta` may be
+    /// negative, e.g. when a transaction is evicted or replaced by a smaller one.
+    fn adjust_mempool_bytes(tx: &mut MemPoolTx, delta: i64) -> Result<(), db_error> {
+        let sql = "UPDATE mempool_stats SET total_bytes = total_bytes + ?1";
+        let args: &[&dyn ToSql] = &[&delta];
+        tx.execute(sql, args)?;
+        Ok(())
+    }
+
+    /// Make room for a transaction of `new_length` bytes and fee `new_tx_fee` being admitted as
+    /// `new_txid`, by evicting existing lowest-fee-rate transactions (other than `new_txid`
+    /// itself -- see `prior_len`) until the running byte total (see `get_mempool_bytes`), counting
+    /// the new transaction in place of whatever prior row it's replacing, is at or under
+    /// `max_mempool_bytes`.  Unlike the bloom-counter de-prioritization in
+    /// `update_bloom_counter`, this actually deletes rows from `mempool`, and reports each
+    /// eviction to `event_observer` as `MemPoolDropReason::EVICTED_LOW_FEE`.
+    ///
+    /// Called *before* the new transaction is inserted, so that if the new transaction would
+    /// itself be the lowest fee-rate entry once admitted -- i.e. it alone exceeds
+    /// `max_mempool_bytes`, or no other transaction in the mempool pays a lower fee rate --
+    /// admission is rejected outright instead of being admitted and then immediately evicted
+    /// again with no signal to the caller.
+    fn evict_txs_for_space(
+        tx: &mut MemPoolTx,
+        new_txid: &Txid,
+        new_tx_fee: u64,
+        new_length: u64,
+        prior_len: Option<u64>,
+        max_mempool_bytes: u64,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+    ) -> Result<(), MemPoolRejection> {
+        let new_entry = MempoolFeeRateEntry {
+            txid: new_txid.clone(),
+            tx_fee: new_tx_fee,
+            length: new_length,
+        };
+
+        let mut evicted = vec![];
+        loop {
+            let bytes_without_new =
+                MemPoolDB::get_mempool_bytes(tx).map_err(MemPoolRejection::DBError)?
+                    - prior_len.unwrap_or(0);
+            if bytes_without_new + new_length <= max_mempool_bytes {
+                break;
+            }
+
+            let sql = "SELECT txid, tx_fee, length FROM mempool WHERE txid != ?1";
+            let args: &[&dyn ToSql] = &[new_txid];
+            let candidates: Vec<MempoolFeeRateEntry> =
+                query_rows(tx, sql, args).map_err(MemPoolRejection::DBError)?;
+            let lowest = candidates.into_iter().min_by(|a, b| a.cmp_fee_rate(b));
+
+            match lowest {
+                Some(lowest) if lowest.cmp_fee_rate(&new_entry) == cmp::Ordering::Less => {
+                    tx.execute("DELETE FROM mempool WHERE txid = ?1", &[&lowest.txid])
+                        .map_err(|e| MemPoolRejection::DBError(db_error::SqliteError(e)))?;
+                    MemPoolDB::adjust_mempool_bytes(tx, -(lowest.length as i64))
+                        .map_err(MemPoolRejection::DBError)?;
+                    evicted.push(lowest.txid);
+                }
+                _ => {
+                    // either nothing is left to evict, or every remaining transaction pays a fee
+                    // rate at least as good as the new one -- the new transaction is the
+                    // eviction target, so reject it instead of admitting and then evicting it.
+                    if let Some(event_observer) = event_observer {
+                        if !evicted.is_empty() {
+                            event_observer
+                                .mempool_txs_dropped(evicted, MemPoolDropReason::EVICTED_LOW_FEE);
+                        }
+                    }
+                    return Err(MemPoolRejection::Other(format!(
+                        "tx {} does not pay a high enough fee rate to fit within the {}-byte mempool cap",
+                        new_txid, max_mempool_bytes
+                    )));
+                }
+            }
+        }
+
+        if let Some(event_observer) = event_observer {
+            if !evicted.is_empty() {
+                event_observer.mempool_txs_dropped(evicted, MemPoolDropReason::EVICTED_LOW_FEE);
+            }
+        }
+
+        Ok(())
+    }
+
     /// Get all transactions across all tips
     #[cfg(test)]
     pub fn get_all_txs(conn: &DBConn) -> Result<Vec<MemPoolTxInfo>, db_error> {
@@ -797,10 +1789,12 @@ impl MemPoolDB {
                           sponsor_nonce,
                           tx_fee,
                           length,
+                          estimated_cost,
                           consensus_hash,
                           block_header_hash,
                           height,
-                          accept_time
+                          accept_time,
+                          coinbase_height
                           FROM mempool WHERE {0}_address = ?1 AND {0}_nonce = ?2",
             if is_origin { "origin" } else { "sponsor" }
         );
@@ -808,6 +1802,220 @@ impl MemPoolDB {
         query_row(conn, &sql, args)
     }
 
+    /// What's the next origin nonce that the mempool will accept from `origin_address`, given
+    /// that its account nonce (at the chain tip a new transaction is being considered for) is
+    /// `account_nonce`?  This walks the contiguous run of origin nonces already accepted into
+    /// the mempool for this address, starting at `account_nonce`, and returns one past the end
+    /// of that run -- capped at `account_nonce + MAXIMUM_MEMPOOL_TX_CHAINING`.  A transaction
+    /// whose origin nonce is greater than this value would leave a gap that `iterate_candidates`
+    /// can never pick up, since it only ever considers the transaction whose nonce matches the
+    /// account's current nonce.
+    pub fn get_next_mineable_nonce(
+        conn: &DBConn,
+        origin_address: &StacksAddress,
+        account_nonce: u64,
+    ) -> Result<u64, db_error> {
+        let mut next_nonce = account_nonce;
+        while next_nonce < account_nonce.saturating_add(MAXIMUM_MEMPOOL_TX_CHAINING) {
+            if MemPoolDB::get_tx_metadata_by_address(conn, true, origin_address, next_nonce)?
+                .is_none()
+            {
+                break;
+            }
+            next_nonce += 1;
+        }
+        Ok(next_nonce)
+    }
+
+    /// Is there a transaction from `origin_address` buffered in `pending_mempool` at
+    /// `origin_nonce`?
+    fn has_pending_tx(
+        conn: &DBConn,
+        origin_address: &StacksAddress,
+        origin_nonce: u64,
+    ) -> Result<bool, db_error> {
+        let sql =
+            "SELECT 1 FROM pending_mempool WHERE origin_address = ?1 AND origin_nonce = ?2";
+        let args: &[&dyn ToSql] = &[&origin_address.to_string(), &u64_to_sql(origin_nonce)?];
+        let found: Option<i64> = query_row(conn, sql, args)?;
+        Ok(found.is_some())
+    }
+
+    /// What nonce should the next transaction from `origin_address` use, given that its
+    /// confirmed (on-chain) nonce is `account_nonce`?  This walks past both the transactions
+    /// already admitted into `mempool` and those buffered in `pending_mempool` for this
+    /// address, so that a wallet chaining several transactions under strict sequential
+    /// admission (see `MemPoolAdmitter::set_strict_sequential_admission`) always gets a nonce
+    /// that isn't already spoken for.
+    pub fn get_next_nonce(
+        conn: &DBConn,
+        origin_address: &StacksAddress,
+        account_nonce: u64,
+    ) -> Result<u64, db_error> {
+        let mut next_nonce = account_nonce;
+        loop {
+            let in_mempool =
+                MemPoolDB::get_tx_metadata_by_address(conn, true, origin_address, next_nonce)?
+                    .is_some();
+            let in_pending = MemPoolDB::has_pending_tx(conn, origin_address, next_nonce)?;
+            if !in_mempool && !in_pending {
+                break;
+            }
+            next_nonce += 1;
+        }
+        Ok(next_nonce)
+    }
+
+    /// Buffer a transaction whose origin nonce is ahead of the account's confirmed nonce into
+    /// `pending_mempool`, so it can be promoted into `mempool` once the nonces before it are
+    /// filled in (see `promote_pending_tx`).
+    fn store_pending_tx(
+        mempool_tx: &mut MemPoolTx,
+        txid: &Txid,
+        tx_bytes: Vec<u8>,
+        tx_fee: u64,
+        length: u64,
+        origin_address: &StacksAddress,
+        origin_nonce: u64,
+        sponsor_address: &StacksAddress,
+        sponsor_nonce: u64,
+        consensus_hash: &ConsensusHash,
+        block_header_hash: &BlockHeaderHash,
+    ) -> Result<(), db_error> {
+        let sql = "INSERT OR REPLACE INTO pending_mempool (
+            txid,
+            origin_address,
+            origin_nonce,
+            sponsor_address,
+            sponsor_nonce,
+            tx_fee,
+            length,
+            consensus_hash,
+            block_header_hash,
+            accept_time,
+            tx)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)";
+        let args: &[&dyn ToSql] = &[
+            txid,
+            &origin_address.to_string(),
+            &u64_to_sql(origin_nonce)?,
+            &sponsor_address.to_string(),
+            &u64_to_sql(sponsor_nonce)?,
+            &u64_to_sql(tx_fee)?,
+            &u64_to_sql(length)?,
+            consensus_hash,
+            block_header_hash,
+            &u64_to_sql(get_epoch_time_secs())?,
+            &tx_bytes,
+        ];
+        mempool_tx.execute(sql, args)?;
+        Ok(())
+    }
+
+    /// Promote the transaction (if any) buffered in `pending_mempool` for `origin_address` at
+    /// `origin_nonce` into the main `mempool` table, re-running it through the normal admission
+    /// path (`try_add_tx`) at the chain tip it was originally submitted against.  Returns whether
+    /// a transaction was promoted.
+    fn promote_one_pending_tx(
+        mempool_tx: &mut MemPoolTx,
+        chainstate: &mut StacksChainState,
+        origin_address: &StacksAddress,
+        origin_nonce: u64,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+    ) -> Result<bool, MemPoolRejection> {
+        let sql =
+            "SELECT * FROM pending_mempool WHERE origin_address = ?1 AND origin_nonce = ?2";
+        let args: &[&dyn ToSql] = &[
+            &origin_address.to_string(),
+            &u64_to_sql(origin_nonce).map_err(MemPoolRejection::DBError)?,
+        ];
+        let pending_tx: Option<PendingMemPoolTx> =
+            query_row(mempool_tx, sql, args).map_err(MemPoolRejection::DBError)?;
+
+        let pending_tx = match pending_tx {
+            Some(pending_tx) => pending_tx,
+            None => return Ok(false),
+        };
+
+        let delete_sql = "DELETE FROM pending_mempool WHERE txid = ?1";
+        mempool_tx
+            .execute(delete_sql, &[&pending_tx.txid])
+            .map_err(|e| MemPoolRejection::DBError(db_error::SqliteError(e)))?;
+
+        let height = match chainstate
+            .get_stacks_block_height(&pending_tx.consensus_hash, &pending_tx.block_header_hash)
+        {
+            Ok(Some(h)) => h,
+            Ok(None) => {
+                if pending_tx.consensus_hash == FIRST_BURNCHAIN_CONSENSUS_HASH {
+                    0
+                } else {
+                    // the tip this tx was deferred against is no longer recognized (e.g. a
+                    // reorg) -- drop it rather than admit it against a height of 0.
+                    return Ok(false);
+                }
+            }
+            Err(e) => {
+                return Err(MemPoolRejection::Other(format!(
+                    "Failed to load chain tip: {:?}",
+                    &e
+                )));
+            }
+        };
+
+        MemPoolDB::try_add_tx(
+            mempool_tx,
+            chainstate,
+            &pending_tx.consensus_hash,
+            &pending_tx.block_header_hash,
+            pending_tx.txid.clone(),
+            pending_tx.tx_bytes,
+            pending_tx.tx_fee,
+            height,
+            origin_address,
+            origin_nonce,
+            &pending_tx.sponsor_address,
+            pending_tx.sponsor_nonce,
+            event_observer,
+        )?;
+
+        Ok(true)
+    }
+
+    /// Promote transactions buffered in `pending_mempool` for `origin_address`, starting at
+    /// `origin_nonce`, into the main `mempool` table.  Called after a transaction at
+    /// `origin_nonce - 1` becomes ready -- whether by being admitted into the mempool or by being
+    /// mined -- since that's what unblocks this nonce.  A single admission can unblock an entire
+    /// chain of buffered nonces (an account at nonce 4 may have nonces 5, 6, and 7 all sitting in
+    /// `pending_mempool`), so this keeps promoting `origin_nonce`, `origin_nonce + 1`, ... until
+    /// one of them has nothing buffered for it.  Returns whether at least one transaction was
+    /// promoted.
+    pub fn promote_pending_tx(
+        mempool_tx: &mut MemPoolTx,
+        chainstate: &mut StacksChainState,
+        origin_address: &StacksAddress,
+        origin_nonce: u64,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+    ) -> Result<bool, MemPoolRejection> {
+        let mut promoted_any = false;
+        let mut next_nonce = origin_nonce;
+        loop {
+            let promoted = MemPoolDB::promote_one_pending_tx(
+                mempool_tx,
+                chainstate,
+                origin_address,
+                next_nonce,
+                event_observer,
+            )?;
+            if !promoted {
+                break;
+            }
+            promoted_any = true;
+            next_nonce = next_nonce.saturating_add(1);
+        }
+        Ok(promoted_any)
+    }
+
     fn are_blocks_in_same_fork(
         chainstate: &mut StacksChainState,
         first_consensus_hash: &ConsensusHash,
@@ -841,15 +2049,18 @@ impl MemPoolDB {
         }
     }
 
-    /// Remove all txids at the given height from the bloom counter.
+    /// Remove all txids at the given coinbase height from the bloom counter.
     /// Used to clear out txids that are now outside the bloom counter's depth.
-    fn prune_bloom_counter(tx: &mut MemPoolTx, target_height: u64) -> Result<(), MemPoolRejection> {
-        let sql = "SELECT txid FROM mempool WHERE height = ?1 AND NOT EXISTS (SELECT 1 FROM removed_txids WHERE txid = mempool.txid)";
-        let args: &[&dyn ToSql] = &[&u64_to_sql(target_height)?];
+    fn prune_bloom_counter(
+        tx: &mut MemPoolTx,
+        target_coinbase_height: u64,
+    ) -> Result<(), MemPoolRejection> {
+        let sql = "SELECT txid FROM mempool WHERE coinbase_height = ?1 AND NOT EXISTS (SELECT 1 FROM removed_txids WHERE txid = mempool.txid)";
+        let args: &[&dyn ToSql] = &[&u64_to_sql(target_coinbase_height)?];
         let txids: Vec<Txid> = query_rows(tx, sql, args)?;
         let num_txs = txids.len();
 
-        debug!("Prune bloom counter from height {}", target_height);
+        debug!("Prune bloom counter from coinbase height {}", target_coinbase_height);
 
         // keep borrow-checker happy
         let bloom_counter = tx.take_bloom_state();
@@ -862,41 +2073,45 @@ impl MemPoolDB {
         }
 
         debug!(
-            "Pruned bloom filter at height {}: removed {} txs",
-            target_height, num_txs
+            "Pruned bloom filter at coinbase height {}: removed {} txs",
+            target_coinbase_height, num_txs
         );
         tx.replace_bloom_state(bloom_counter);
         Ok(())
     }
 
     /// Add the txid to the bloom counter in the mempool DB.
-    /// If this is the first txid at this block height, then also garbage-collect the bloom counter to remove no-longer-recent transactions.
+    /// If this is the first txid at this coinbase height, then also garbage-collect the bloom
+    /// counter to remove no-longer-recent transactions.  Keying this off of `coinbase_height`
+    /// rather than the raw, fork-sensitive `height` means a sortition-level reorg that rewinds
+    /// `height` doesn't fool this into re-pruning a window it already pruned, or into stalling a
+    /// window advance it already made (see `MemPoolDB::clear_before_coinbase_height`).
     /// If the bloom counter is saturated -- i.e. it represents more than MAX_BLOOM_COUNTER_TXS
     /// transactions -- then pick another transaction to evict from the bloom filter and return its txid.
     /// (Note that no transactions are ever removed from the mempool; we just don't prioritize them
     /// in the bloom filter).
     fn update_bloom_counter(
         tx: &mut MemPoolTx,
-        height: u64,
+        coinbase_height: u64,
         txid: &Txid,
-        prior_txid: Option<Txid>,
+        prior_txids: &[Txid],
     ) -> Result<Option<Txid>, MemPoolRejection> {
-        // is this the first-ever txid at this height?
-        let sql = "SELECT 1 FROM mempool WHERE height = ?1";
-        let args: &[&dyn ToSql] = &[&u64_to_sql(height)?];
+        // is this the first-ever txid at this coinbase height?
+        let sql = "SELECT 1 FROM mempool WHERE coinbase_height = ?1";
+        let args: &[&dyn ToSql] = &[&u64_to_sql(coinbase_height)?];
         let present: Option<i64> = query_row(tx, sql, args)?;
-        if present.is_none() && height > (BLOOM_COUNTER_DEPTH as u64) {
-            // this is the first-ever tx at this height.
+        if present.is_none() && coinbase_height > (BLOOM_COUNTER_DEPTH as u64) {
+            // this is the first-ever tx at this coinbase height.
             // which means, the bloom filter window has advanced.
             // which means, we need to remove all the txs that are now out of the window.
-            MemPoolDB::prune_bloom_counter(tx, height - (BLOOM_COUNTER_DEPTH as u64))?;
+            MemPoolDB::prune_bloom_counter(tx, coinbase_height - (BLOOM_COUNTER_DEPTH as u64))?;
         }
 
         // keep borrow-checker happy
         let bloom_counter = tx.take_bloom_state();
 
-        // remove replaced transaction
-        if let Some(prior_txid) = prior_txid {
+        // remove replaced transactions
+        for prior_txid in prior_txids.iter() {
             bloom_counter.remove_raw(&mut tx.tx, &prior_txid.0)?;
         }
 
@@ -905,11 +2120,12 @@ impl MemPoolDB {
         let evict_txid = {
             let num_recents = MemPoolDB::get_num_recent_txs(&tx.tx)?;
             if num_recents >= MAX_BLOOM_COUNTER_TXS.into() {
-                // for now, remove lowest-fee tx in the recent tx set.
-                // TODO: In the future, do it by lowest fee rate
-                let sql = "SELECT txid FROM mempool WHERE height > ?1 AND NOT EXISTS (SELECT 1 FROM removed_txids WHERE txid = mempool.txid) ORDER BY tx_fee ASC LIMIT 1";
+                // this only deprioritizes a tx within the bloom filter -- it does not remove it
+                // from `mempool` (see `evict_txs_for_space` for the real, fee-rate-based
+                // eviction that actually deletes rows to stay under `max_mempool_bytes`).
+                let sql = "SELECT txid FROM mempool WHERE coinbase_height > ?1 AND NOT EXISTS (SELECT 1 FROM removed_txids WHERE txid = mempool.txid) ORDER BY tx_fee ASC LIMIT 1";
                 let args: &[&dyn ToSql] = &[&u64_to_sql(
-                    height.saturating_sub(BLOOM_COUNTER_DEPTH as u64),
+                    coinbase_height.saturating_sub(BLOOM_COUNTER_DEPTH as u64),
                 )?];
                 let evict_txid: Option<Txid> = query_row(&tx.tx, sql, args)?;
                 if let Some(evict_txid) = evict_txid {
@@ -953,8 +2169,52 @@ impl MemPoolDB {
         Ok(())
     }
 
-    /// Add a transaction to the mempool.  If it already exists, then replace it if the given fee
-    /// is higher than the one that's already there.
+    /// Does a replacement transaction's fee rate (`new_fee / new_len`) beat the incumbent's
+    /// (`prior_fee / prior_len`) by at least `min_bump_pct` percent?  Compared via
+    /// cross-multiplication (`new_fee * prior_len * 100` vs `prior_fee * new_len * (100 +
+    /// min_bump_pct)`) to avoid the floating-point drift a direct rate division would introduce.
+    fn passes_min_rbf_bump(
+        new_fee: u64,
+        new_len: u64,
+        prior_fee: u64,
+        prior_len: u64,
+        min_bump_pct: u64,
+    ) -> bool {
+        let lhs = (new_fee as u128) * (prior_len as u128) * 100;
+        let rhs = (prior_fee as u128) * (new_len as u128) * (100 + min_bump_pct as u128);
+        lhs >= rhs
+    }
+
+    /// Does a replacement transaction's fee rate (`new_fee / new_len`) beat the incumbent's
+    /// (`prior_fee / prior_len`) by at least a flat `min_increment` fee-per-byte, i.e. is
+    /// `new_fee/new_len >= prior_fee/prior_len + min_increment`?  Compared via
+    /// cross-multiplication (`new_fee * prior_len` vs `prior_fee * new_len + min_increment *
+    /// new_len * prior_len`) to avoid the floating-point drift a direct rate division would
+    /// introduce.
+    fn passes_min_rbf_bump_increment(
+        new_fee: u64,
+        new_len: u64,
+        prior_fee: u64,
+        prior_len: u64,
+        min_increment: u64,
+    ) -> bool {
+        let lhs = (new_fee as u128) * (prior_len as u128);
+        let rhs = (prior_fee as u128) * (new_len as u128)
+            + (min_increment as u128) * (new_len as u128) * (prior_len as u128);
+        lhs >= rhs
+    }
+
+    /// Add a transaction to the mempool.  A new transaction can collide on its `(address, nonce)`
+    /// slot in up to two distinct ways at once -- a prior tx already occupying its origin slot,
+    /// and a *different* prior tx already occupying its sponsor slot, which is the common case
+    /// for a sponsored transaction whose origin and sponsor are different accounts.  Both
+    /// conflicts are gathered into a single set and replaced atomically: admission only succeeds
+    /// if the incoming tx's fee rate beats *every* same-fork conflict's by the margin the
+    /// admitter's `rbf_requirement` selects -- `min_rbf_fee_rate_bump_pct` percent (see
+    /// `passes_min_rbf_bump`) or a flat `min_rbf_fee_rate_increment` per byte (see
+    /// `passes_min_rbf_bump_increment`) -- and on
+    /// success every conflicting txid is deleted within this same `MemPoolTx` (see
+    /// `mempool_db_test_rbf_nonce_conflicts`'s combined-collision case).
     /// Carry out the mempool admission test before adding.
     /// Don't call directly; use submit().
     /// This is `pub` only for testing.
@@ -972,75 +2232,151 @@ impl MemPoolDB {
         sponsor_address: &StacksAddress,
         sponsor_nonce: u64,
         event_observer: Option<&dyn MemPoolEventDispatcher>,
-    ) -> Result<(), MemPoolRejection> {
+    ) -> Result<Vec<Txid>, MemPoolRejection> {
         let length = tx_bytes.len() as u64;
+        let estimated_cost = match StacksTransaction::consensus_deserialize(&mut &tx_bytes[..]) {
+            Ok(parsed_tx) => estimate_tx_cost(length, &parsed_tx.payload),
+            Err(_) => length,
+        };
 
-        // do we already have txs with either the same origin nonce or sponsor nonce ?
-        let prior_tx = {
-            match MemPoolDB::get_tx_metadata_by_address(tx, true, origin_address, origin_nonce)? {
-                Some(prior_tx) => Some(prior_tx),
-                None => MemPoolDB::get_tx_metadata_by_address(
-                    tx,
-                    false,
-                    sponsor_address,
-                    sponsor_nonce,
-                )?,
+        // the monotonic, fork-agnostic tenure height of the chain tip this tx is admitted
+        // against -- unlike `height`, this doesn't move backward across a sortition-level reorg,
+        // so sync and GC key off of it instead (see `clear_before_coinbase_height`).  Fall back
+        // to `height` if the tip's coinbase height isn't available (e.g. the genesis tip).
+        let coinbase_height = match chainstate.get_coinbase_height(consensus_hash, block_header_hash) {
+            Ok(Some(h)) => h,
+            Ok(None) => height,
+            Err(e) => {
+                return Err(MemPoolRejection::Other(format!(
+                    "Failed to load coinbase height: {:?}",
+                    &e
+                )));
             }
         };
 
-        let mut replace_reason = MemPoolDropReason::REPLACE_BY_FEE;
+        // do we already have txs that collide with this one, either by origin nonce or by
+        // sponsor nonce?  There can be up to two: one matched via the origin slot, and a
+        // distinct one matched via the sponsor slot.
+        let mut conflicts = vec![];
+        if let Some(prior_tx) =
+            MemPoolDB::get_tx_metadata_by_address(tx, true, origin_address, origin_nonce)?
+        {
+            conflicts.push(prior_tx);
+        }
+        if let Some(prior_tx) =
+            MemPoolDB::get_tx_metadata_by_address(tx, false, sponsor_address, sponsor_nonce)?
+        {
+            if !conflicts.iter().any(|c| c.txid == prior_tx.txid) {
+                conflicts.push(prior_tx);
+            }
+        }
 
-        // if so, is this a replace-by-fee? or a replace-in-chain-tip?
-        let add_tx = if let Some(ref prior_tx) = prior_tx {
-            if tx_fee > prior_tx.tx_fee {
-                // is this a replace-by-fee ?
-                debug!(
-                    "Can replace {} with {} for {},{} by fee ({} < {})",
-                    &prior_tx.txid, &txid, origin_address, origin_nonce, &prior_tx.tx_fee, &tx_fee
-                );
-                replace_reason = MemPoolDropReason::REPLACE_BY_FEE;
-                true
-            } else if !MemPoolDB::are_blocks_in_same_fork(
+        // of the conflicting txs, split them into those that can be replaced because they're
+        // in an incompatible fork (always replaceable), and those that are in the same fork as
+        // this new transaction (replaceable only by fee).
+        let mut same_fork_conflicts = vec![];
+        let mut cross_fork_conflicts = vec![];
+        for prior_tx in conflicts.into_iter() {
+            if MemPoolDB::are_blocks_in_same_fork(
                 chainstate,
                 &prior_tx.consensus_hash,
                 &prior_tx.block_header_hash,
                 consensus_hash,
                 block_header_hash,
             )? {
-                // is this a replace-across-fork ?
-                debug!(
-                    "Can replace {} with {} for {},{} across fork",
-                    &prior_tx.txid, &txid, origin_address, origin_nonce
-                );
-                replace_reason = MemPoolDropReason::REPLACE_ACROSS_FORK;
-                true
+                same_fork_conflicts.push(prior_tx);
             } else {
-                // there's a >= fee tx in this fork, cannot add
-                info!("TX conflicts with sponsor/origin nonce in same fork with >= fee";
-                      "new_txid" => %txid, 
-                      "old_txid" => %prior_tx.txid,
-                      "origin_addr" => %origin_address,
-                      "origin_nonce" => origin_nonce,
-                      "sponsor_addr" => %sponsor_address,
-                      "sponsor_nonce" => sponsor_nonce,
-                      "new_fee" => tx_fee,
-                      "old_fee" => prior_tx.tx_fee);
-                false
+                cross_fork_conflicts.push(prior_tx);
             }
-        } else {
-            // no conflicting TX with this origin/sponsor, go ahead and add
-            true
-        };
+        }
+
+        let rbf_requirement = tx.admitter.rbf_requirement;
+        let min_rbf_fee_rate_bump_pct = tx.admitter.min_rbf_fee_rate_bump_pct;
+        let min_rbf_fee_rate_increment = tx.admitter.min_rbf_fee_rate_increment;
+        let insufficient_fee_rate_bump = same_fork_conflicts.iter().any(|prior_tx| {
+            let passes = match rbf_requirement {
+                RbfRequirement::FeeRatePercentage => MemPoolDB::passes_min_rbf_bump(
+                    tx_fee,
+                    length,
+                    prior_tx.tx_fee,
+                    prior_tx.len,
+                    min_rbf_fee_rate_bump_pct,
+                ),
+                RbfRequirement::FeeRateIncrement => MemPoolDB::passes_min_rbf_bump_increment(
+                    tx_fee,
+                    length,
+                    prior_tx.tx_fee,
+                    prior_tx.len,
+                    min_rbf_fee_rate_increment,
+                ),
+            };
+            !passes
+        });
 
-        if !add_tx {
+        if insufficient_fee_rate_bump {
+            // the new transaction does not beat the fee *rate* of every conflicting transaction
+            // that shares this fork by the required margin -- cannot add, and nothing gets
+            // evicted.  This closes off cheaply churning the pool by bumping a tiny tx's
+            // absolute fee by a trivial amount while paying less per byte than the incumbent.
+            info!("TX conflicts with sponsor/origin nonce in same fork without a sufficient fee-rate bump";
+                  "new_txid" => %txid,
+                  "origin_addr" => %origin_address,
+                  "origin_nonce" => origin_nonce,
+                  "sponsor_addr" => %sponsor_address,
+                  "sponsor_nonce" => sponsor_nonce,
+                  "new_fee" => tx_fee,
+                  "new_len" => length,
+                  "rbf_requirement" => format!("{:?}", rbf_requirement),
+                  "min_rbf_fee_rate_bump_pct" => min_rbf_fee_rate_bump_pct,
+                  "min_rbf_fee_rate_increment" => min_rbf_fee_rate_increment);
             return Err(MemPoolRejection::ConflictingNonceInMempool);
         }
 
-        MemPoolDB::update_bloom_counter(
+        for prior_tx in same_fork_conflicts.iter() {
+            debug!(
+                "Can replace {} with {} for {},{} by fee ({} < {})",
+                &prior_tx.txid, &txid, origin_address, origin_nonce, &prior_tx.tx_fee, &tx_fee
+            );
+        }
+        for prior_tx in cross_fork_conflicts.iter() {
+            debug!(
+                "Can replace {} with {} for {},{} across fork",
+                &prior_tx.txid, &txid, origin_address, origin_nonce
+            );
+        }
+
+        let replaced_txs: Vec<(Txid, MemPoolDropReason)> = same_fork_conflicts
+            .iter()
+            .map(|prior_tx| (prior_tx.txid.clone(), MemPoolDropReason::REPLACE_BY_FEE))
+            .chain(
+                cross_fork_conflicts
+                    .iter()
+                    .map(|prior_tx| (prior_tx.txid.clone(), MemPoolDropReason::REPLACE_ACROSS_FORK)),
+            )
+            .collect();
+
+        let replaced_txids: Vec<Txid> = replaced_txs.iter().map(|(txid, _)| txid.clone()).collect();
+
+        MemPoolDB::update_bloom_counter(tx, coinbase_height, &txid, &replaced_txids)?;
+
+        // if a row with this exact txid already exists (e.g. this is a resubmission), only the
+        // size delta should be applied to the cached byte-total below.
+        let prior_len = MemPoolDB::get_tx(tx, &txid)
+            .map_err(MemPoolRejection::DBError)?
+            .map(|prior_tx| prior_tx.metadata.len);
+
+        // make room for this tx *before* inserting it, so that if it would itself be the
+        // eviction target (it alone exceeds the cap, or nothing else in the mempool pays a
+        // lower fee rate), admission is rejected outright instead of succeeding and then
+        // immediately evicting the row we just inserted.
+        MemPoolDB::evict_txs_for_space(
             tx,
-            height,
             &txid,
-            prior_tx.as_ref().map(|tx| tx.txid.clone()),
+            tx_fee,
+            length,
+            prior_len,
+            tx.admitter.max_mempool_bytes,
+            event_observer,
         )?;
 
         let sql = "INSERT OR REPLACE INTO mempool (
@@ -1051,12 +2387,14 @@ impl MemPoolDB {
             sponsor_nonce,
             tx_fee,
             length,
+            estimated_cost,
             consensus_hash,
             block_header_hash,
             height,
             accept_time,
-            tx)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)";
+            tx,
+            coinbase_height)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)";
 
         let args: &[&dyn ToSql] = &[
             &txid,
@@ -1066,11 +2404,13 @@ impl MemPoolDB {
             &u64_to_sql(sponsor_nonce)?,
             &u64_to_sql(tx_fee)?,
             &u64_to_sql(length)?,
+            &u64_to_sql(estimated_cost)?,
             consensus_hash,
             block_header_hash,
             &u64_to_sql(height)?,
             &u64_to_sql(get_epoch_time_secs())?,
             &tx_bytes,
+            &u64_to_sql(coinbase_height)?,
         ];
 
         tx.execute(sql, args)
@@ -1078,12 +2418,29 @@ impl MemPoolDB {
 
         MemPoolDB::update_mempool_pager(tx, &txid)?;
 
-        // broadcast drop event if a tx is being replaced
-        if let (Some(prior_tx), Some(event_observer)) = (prior_tx, event_observer) {
-            event_observer.mempool_txs_dropped(vec![prior_tx.txid], replace_reason);
+        let byte_delta = (length as i64) - prior_len.map(|len| len as i64).unwrap_or(0);
+        MemPoolDB::adjust_mempool_bytes(tx, byte_delta).map_err(MemPoolRejection::DBError)?;
+
+        // broadcast a drop event per evicted tx, grouped by the reason it was evicted
+        if let Some(event_observer) = event_observer {
+            let mut by_fee = vec![];
+            let mut across_fork = vec![];
+            for (replaced_txid, reason) in replaced_txs.into_iter() {
+                match reason {
+                    MemPoolDropReason::REPLACE_BY_FEE => by_fee.push(replaced_txid),
+                    _ => across_fork.push(replaced_txid),
+                }
+            }
+            if !by_fee.is_empty() {
+                event_observer.mempool_txs_dropped(by_fee, MemPoolDropReason::REPLACE_BY_FEE);
+            }
+            if !across_fork.is_empty() {
+                event_observer
+                    .mempool_txs_dropped(across_fork, MemPoolDropReason::REPLACE_ACROSS_FORK);
+            }
         };
 
-        Ok(())
+        Ok(replaced_txids)
     }
 
     /// Garbage-collect the mempool.  Remove transactions that have a given number of
@@ -1116,32 +2473,334 @@ impl MemPoolDB {
         Ok(())
     }
 
-    /// Scan the chain tip for all available transactions (but do not remove them!)
-    pub fn poll(
-        &mut self,
-        consensus_hash: &ConsensusHash,
-        block_hash: &BlockHeaderHash,
-    ) -> Vec<StacksTransaction> {
-        test_debug!("Mempool poll at {}/{}", consensus_hash, block_hash);
-        MemPoolDB::get_txs_after(
-            &self.db,
-            consensus_hash,
-            block_hash,
-            0,
-            (i64::MAX - 1) as u64,
-        )
-        .unwrap_or(vec![])
-        .into_iter()
-        .map(|tx_info| {
-            test_debug!(
-                "Mempool poll {} at {}/{}",
-                &tx_info.tx.txid(),
-                consensus_hash,
-                block_hash
-            );
-            tx_info.tx
-        })
-        .collect()
+    /// Garbage-collect the mempool by coinbase height rather than per-fork `height`.  This is
+    /// the fork-agnostic counterpart to `garbage_collect`: it only evicts transactions once the
+    /// monotonic tenure coordinate has passed them by, so it doesn't prematurely collect
+    /// transactions that are merely sitting behind a Bitcoin reorg that rewound `height` without
+    /// rewinding `coinbase_height`.
+    pub fn garbage_collect_by_coinbase_height(
+        tx: &mut MemPoolTx,
+        min_coinbase_height: u64,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+    ) -> Result<(), db_error> {
+        let args: &[&dyn ToSql] = &[&u64_to_sql(min_coinbase_height)?];
+
+        if let Some(event_observer) = event_observer {
+            let sql = "SELECT txid FROM mempool WHERE coinbase_height < ?1";
+            let txids = query_rows(tx, sql, args)?;
+            event_observer.mempool_txs_dropped(txids, MemPoolDropReason::STALE_COLLECT);
+        }
+
+        let sql = "DELETE FROM mempool WHERE coinbase_height < ?1";
+
+        tx.execute(sql, args)?;
+        increment_stx_mempool_gc();
+        Ok(())
+    }
+
+    /// Evict every mempool transaction whose coinbase height is older than
+    /// `min_coinbase_height`.  See `garbage_collect_by_coinbase_height`.
+    pub fn clear_before_coinbase_height(&mut self, min_coinbase_height: u64) -> Result<(), db_error> {
+        let mut tx = self.tx_begin()?;
+        MemPoolDB::garbage_collect_by_coinbase_height(&mut tx, min_coinbase_height, None)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Evict every mempool transaction accepted more than `ttl` seconds ago.  Unlike
+    /// `garbage_collect`/`garbage_collect_by_coinbase_height`, which only fire once a
+    /// transaction has been left behind by the chain tip, this is a pure wall-clock sweep: it
+    /// catches transactions whose nonce will simply never become reachable (e.g. an account
+    /// nonce that raced ahead of it on a fork this node never saw) and which would otherwise sit
+    /// in the mempool -- and the bloom counter/sync payload -- forever.
+    pub fn garbage_collect_expired(
+        tx: &mut MemPoolTx,
+        ttl: u64,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+    ) -> Result<(), db_error> {
+        let min_accept_time = get_epoch_time_secs().saturating_sub(ttl);
+        let args: &[&dyn ToSql] = &[&u64_to_sql(min_accept_time)?];
+
+        if let Some(event_observer) = event_observer {
+            let sql = "SELECT txid FROM mempool WHERE accept_time < ?1";
+            let txids = query_rows(tx, sql, args)?;
+            event_observer.mempool_txs_dropped(txids, MemPoolDropReason::TTL_EXPIRED);
+        }
+
+        let sql = "DELETE FROM mempool WHERE accept_time < ?1";
+
+        tx.execute(sql, args)?;
+        increment_stx_mempool_gc();
+        Ok(())
+    }
+
+    /// Evict every mempool transaction whose accept time is more than `ttl` seconds in the
+    /// past.  See `garbage_collect_expired`.
+    pub fn clear_expired(&mut self, ttl: u64) -> Result<(), db_error> {
+        let mut tx = self.tx_begin()?;
+        MemPoolDB::garbage_collect_expired(&mut tx, ttl, None)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Evict every mempool transaction whose origin or sponsor nonce sits more than
+    /// `max_nonce_gap` away from that address's confirmed nonce, per `account_nonces` -- same
+    /// input shape as `blocks_confirmed`, since the caller (the block-processing code that
+    /// already tracks confirmed nonces) is in the best position to supply them.  Unlike
+    /// `blocks_confirmed`, which only reaps nonces the tip has already passed (`<= confirmed`),
+    /// this also reaps nonces so far *ahead* of the confirmed one that `iterate_candidates` could
+    /// never walk a chain of admitted transactions far enough to reach them -- e.g. a tx
+    /// resubmitted against a stale nonce that will never be filled in.
+    ///
+    /// Every evicted txid is pruned from the bloom counter and reported to `event_observer` as
+    /// `MemPoolDropReason::STALE_NONCE`, same as `blocks_confirmed`.
+    pub fn garbage_collect_stale_nonces(
+        &mut self,
+        account_nonces: &[(StacksAddress, u64)],
+        max_nonce_gap: u64,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+    ) -> Result<(), db_error> {
+        let mut mempool_tx = self.tx_begin()?;
+        let mut dropped_txids = vec![];
+
+        for (address, confirmed_nonce) in account_nonces.iter() {
+            for is_origin in [true, false].iter() {
+                let col = if *is_origin { "origin" } else { "sponsor" };
+                let addr_str = address.to_string();
+
+                let min_reachable_nonce = confirmed_nonce.saturating_sub(max_nonce_gap);
+                let max_reachable_nonce = confirmed_nonce.saturating_add(max_nonce_gap);
+
+                let sql = format!(
+                    "SELECT txid FROM mempool WHERE {0}_address = ?1 AND ({0}_nonce < ?2 OR {0}_nonce > ?3)",
+                    col
+                );
+                let args: &[&dyn ToSql] = &[
+                    &addr_str,
+                    &u64_to_sql(min_reachable_nonce)?,
+                    &u64_to_sql(max_reachable_nonce)?,
+                ];
+                let txids: Vec<Txid> = query_rows(&mempool_tx, &sql, args)?;
+                if txids.is_empty() {
+                    continue;
+                }
+
+                let bloom_counter = mempool_tx.take_bloom_state();
+                for txid in txids.iter() {
+                    bloom_counter.remove_raw(&mut mempool_tx.tx, &txid.0)?;
+                }
+                mempool_tx.replace_bloom_state(bloom_counter);
+
+                let del_sql = format!(
+                    "DELETE FROM mempool WHERE {0}_address = ?1 AND ({0}_nonce < ?2 OR {0}_nonce > ?3)",
+                    col
+                );
+                mempool_tx.execute(&del_sql, args)?;
+
+                dropped_txids.extend(txids);
+            }
+        }
+
+        mempool_tx.commit()?;
+
+        if let Some(event_observer) = event_observer {
+            if !dropped_txids.is_empty() {
+                event_observer.mempool_txs_dropped(dropped_txids, MemPoolDropReason::STALE_NONCE);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// For each `(address, nonce)` a newly-confirmed block reports, drop every mempool tx from
+    /// that address -- whether it's acting as origin or sponsor -- whose nonce is now `<= nonce`,
+    /// since it's either the tx that just got mined or one that a conflicting tx beat it to the
+    /// punch.  Unlike `garbage_collect`, which only looks at confirmation depth, this gives an
+    /// immediate cleanup as soon as a block is accepted, rather than waiting for the sweep.
+    ///
+    /// Dropping those transactions can also orphan whatever this address had queued up behind
+    /// them: if nonce `nonce + 1` isn't present for a role (origin or sponsor) after the drop,
+    /// then every later-nonced tx in that role is stranded -- `iterate_candidates` only ever
+    /// offers up the transaction whose nonce matches the account's current nonce, so a tx with no
+    /// predecessor in the pool can never be reached -- and is removed as well.
+    ///
+    /// Every removed txid is pruned from the bloom counter (see `prune_bloom_counter`) and
+    /// reported to `event_observer` as `MemPoolDropReason::STALE_NONCE`.
+    pub fn blocks_confirmed(
+        &mut self,
+        account_nonces: &[(StacksAddress, u64)],
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+    ) -> Result<(), db_error> {
+        let mut mempool_tx = self.tx_begin()?;
+        let mut dropped_txids = vec![];
+
+        for (address, confirmed_nonce) in account_nonces.iter() {
+            for is_origin in [true, false].iter() {
+                let col = if *is_origin { "origin" } else { "sponsor" };
+                let addr_str = address.to_string();
+
+                let sql = format!(
+                    "SELECT txid FROM mempool WHERE {0}_address = ?1 AND {0}_nonce <= ?2",
+                    col
+                );
+                let args: &[&dyn ToSql] = &[&addr_str, &u64_to_sql(*confirmed_nonce)?];
+                let mut txids: Vec<Txid> = query_rows(&mempool_tx, &sql, args)?;
+
+                // a gap at `confirmed_nonce + 1` strands every later-nonced tx in this role --
+                // there's nothing left in the mempool for them to chain off of
+                let has_successor = MemPoolDB::get_tx_metadata_by_address(
+                    &mempool_tx,
+                    *is_origin,
+                    address,
+                    confirmed_nonce + 1,
+                )?
+                .is_some();
+
+                if !has_successor {
+                    let orphan_sql = format!(
+                        "SELECT txid FROM mempool WHERE {0}_address = ?1 AND {0}_nonce > ?2",
+                        col
+                    );
+                    let orphans: Vec<Txid> = query_rows(&mempool_tx, &orphan_sql, args)?;
+                    txids.extend(orphans);
+                }
+
+                if txids.is_empty() {
+                    continue;
+                }
+
+                // keep borrow-checker happy
+                let bloom_counter = mempool_tx.take_bloom_state();
+                for txid in txids.iter() {
+                    bloom_counter.remove_raw(&mut mempool_tx.tx, &txid.0)?;
+                }
+                mempool_tx.replace_bloom_state(bloom_counter);
+
+                let del_sql = format!(
+                    "DELETE FROM mempool WHERE {0}_address = ?1 AND {0}_nonce <= ?2",
+                    col
+                );
+                mempool_tx.execute(&del_sql, args)?;
+
+                if !has_successor {
+                    let orphan_del_sql = format!(
+                        "DELETE FROM mempool WHERE {0}_address = ?1 AND {0}_nonce > ?2",
+                        col
+                    );
+                    mempool_tx.execute(&orphan_del_sql, args)?;
+                }
+
+                dropped_txids.extend(txids);
+            }
+        }
+
+        mempool_tx.commit()?;
+
+        if let Some(event_observer) = event_observer {
+            if !dropped_txids.is_empty() {
+                event_observer.mempool_txs_dropped(dropped_txids, MemPoolDropReason::STALE_NONCE);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Flag `txid` as having been reintroduced from an orphaned fork (see
+    /// `reinject_orphaned_block`), so the tie-break in `iterate_candidates` prefers it over a
+    /// freshly-arrived tx of the same fee rate.  No-op if `txid` isn't in the mempool (e.g. it
+    /// was re-admitted and then immediately replaced by something else).
+    fn mark_reintroduced(tx: &mut MemPoolTx, txid: &Txid) -> Result<(), db_error> {
+        let sql = "UPDATE mempool SET reintroduced = 1 WHERE txid = ?1";
+        tx.execute(sql, &[txid])?;
+        Ok(())
+    }
+
+    /// Resubmit every transaction from `orphaned_block` -- the loser of a reorg -- against the
+    /// new canonical tip `new_tip`, so that a user's transaction isn't simply lost because the
+    /// block that carried it fell off the chain.  `try_add_tx`'s existing cross-fork handling
+    /// (see `are_blocks_in_same_fork`) already treats a conflicting tx left behind on the old
+    /// fork as unconditionally replaceable (`MemPoolDropReason::REPLACE_ACROSS_FORK`), so the
+    /// only thing that can still keep one of these out is a transaction that's already been
+    /// mined into the new fork.  Every tx that re-admits successfully is flagged `reintroduced`
+    /// (see `mark_reintroduced`).
+    pub fn reinject_orphaned_block(
+        &mut self,
+        chainstate: &mut StacksChainState,
+        orphaned_consensus_hash: &ConsensusHash,
+        orphaned_block_hash: &BlockHeaderHash,
+        new_tip: &(ConsensusHash, BlockHeaderHash),
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+    ) -> Result<(), ChainstateError> {
+        let blocks_path = StacksChainState::blocks_path(&chainstate.root_path);
+        let orphaned_block = match StacksChainState::load_block(
+            &blocks_path,
+            orphaned_consensus_hash,
+            orphaned_block_hash,
+        )? {
+            Some(block) => block,
+            None => {
+                debug!(
+                    "No block data for orphaned block {}/{}; nothing to reinject",
+                    orphaned_consensus_hash, orphaned_block_hash
+                );
+                return Ok(());
+            }
+        };
+
+        let mut mempool_tx = self.tx_begin()?;
+        for tx in orphaned_block.txs.iter() {
+            let txid = tx.txid();
+            match MemPoolDB::tx_submit(
+                &mut mempool_tx,
+                chainstate,
+                &new_tip.0,
+                &new_tip.1,
+                tx,
+                true,
+                event_observer,
+            ) {
+                Ok(()) => {
+                    MemPoolDB::mark_reintroduced(&mut mempool_tx, &txid)?;
+                }
+                Err(rejection) => {
+                    debug!(
+                        "Did not reinject orphaned tx {} onto new tip {}/{}: {:?}",
+                        &txid, &new_tip.0, &new_tip.1, &rejection
+                    );
+                }
+            }
+        }
+        mempool_tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Scan the chain tip for all available transactions (but do not remove them!)
+    pub fn poll(
+        &mut self,
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+    ) -> Vec<StacksTransaction> {
+        test_debug!("Mempool poll at {}/{}", consensus_hash, block_hash);
+        MemPoolDB::get_txs_after(
+            &self.db,
+            consensus_hash,
+            block_hash,
+            0,
+            (i64::MAX - 1) as u64,
+        )
+        .unwrap_or(vec![])
+        .into_iter()
+        .map(|tx_info| {
+            test_debug!(
+                "Mempool poll {} at {}/{}",
+                &tx_info.tx.txid(),
+                consensus_hash,
+                block_hash
+            );
+            tx_info.tx
+        })
+        .collect()
     }
 
     /// Submit a transaction to the mempool at a particular chain tip.
@@ -1182,6 +2841,11 @@ impl MemPoolDB {
         };
 
         let txid = tx.txid();
+
+        if MemPoolDB::is_txid_blacklisted(mempool_tx, &txid).map_err(MemPoolRejection::DBError)? {
+            return Err(MemPoolRejection::TxAlreadyBlacklisted(txid));
+        }
+
         let mut tx_data = vec![];
         tx.consensus_serialize(&mut tx_data)
             .map_err(MemPoolRejection::SerializationFailure)?;
@@ -1197,14 +2861,71 @@ impl MemPoolDB {
                 (origin_address.clone(), origin_nonce)
             };
 
+        // if we've already deterministically rejected this exact txid against this exact tip,
+        // don't pay for another round-trip through `admitter.will_admit_tx` (which consults
+        // chainstate) just to land on the same answer.
+        if let Some(rejection) = mempool_tx.recent_rejects.check(
+            consensus_hash,
+            block_hash,
+            &txid,
+            &origin_address,
+            origin_nonce,
+        ) {
+            return Err(rejection);
+        }
+
         if do_admission_checks {
             mempool_tx
                 .admitter
                 .set_block(&block_hash, (*consensus_hash).clone());
-            mempool_tx.admitter.will_admit_tx(chainstate, tx, len)?;
+            let origin_account_nonce = mempool_tx.admitter.will_admit_tx(chainstate, tx, len)?;
+
+            if mempool_tx.admitter.strict_sequential_admission {
+                // Under strict sequential admission, only the transaction at exactly the
+                // account's confirmed nonce is "ready" -- anything strictly ahead of it is
+                // buffered in `pending_mempool` until the nonces before it fill in, rather than
+                // being admitted into `mempool` (where `iterate_candidates` would otherwise have
+                // to reason about gaps) or rejected outright.
+                if origin_nonce > origin_account_nonce {
+                    MemPoolDB::store_pending_tx(
+                        mempool_tx,
+                        &txid,
+                        tx_data,
+                        tx_fee,
+                        len,
+                        &origin_address,
+                        origin_nonce,
+                        &sponsor_address,
+                        sponsor_nonce,
+                        consensus_hash,
+                        block_hash,
+                    )
+                    .map_err(MemPoolRejection::DBError)?;
+                    return Ok(());
+                }
+            } else {
+                // Reject transactions that would leave a gap in this origin's mempool nonce
+                // sequence: `iterate_candidates` only ever considers the transaction whose nonce
+                // matches the account's current nonce, so anything beyond the next mineable nonce
+                // would simply sit in the mempool forever, consuming space.
+                let next_mineable_nonce = MemPoolDB::get_next_mineable_nonce(
+                    mempool_tx,
+                    &origin_address,
+                    origin_account_nonce,
+                )
+                .map_err(MemPoolRejection::DBError)?;
+                if origin_nonce > next_mineable_nonce {
+                    let rejection =
+                        MemPoolRejection::TooMuchChaining(origin_address.clone(), origin_nonce);
+                    mempool_tx
+                        .recent_rejects
+                        .record(consensus_hash, block_hash, txid, &rejection);
+                    return Err(rejection);
+                }
+            }
         }
 
-        MemPoolDB::try_add_tx(
+        if let Err(rejection) = MemPoolDB::try_add_tx(
             mempool_tx,
             chainstate,
             &consensus_hash,
@@ -1218,7 +2939,24 @@ impl MemPoolDB {
             &sponsor_address,
             sponsor_nonce,
             event_observer,
-        )?;
+        ) {
+            mempool_tx
+                .recent_rejects
+                .record(consensus_hash, block_hash, txid, &rejection);
+            return Err(rejection);
+        }
+
+        if mempool_tx.admitter.strict_sequential_admission {
+            // This tx at `origin_nonce` is now ready, so the tx (if any) buffered for
+            // `origin_nonce + 1` can be promoted out of `pending_mempool`.
+            MemPoolDB::promote_pending_tx(
+                mempool_tx,
+                chainstate,
+                &origin_address,
+                origin_nonce.saturating_add(1),
+                event_observer,
+            )?;
+        }
 
         if let Err(e) = monitoring::mempool_accepted(&txid, &chainstate.root_path) {
             warn!("Failed to monitor TX receive: {:?}", e; "txid" => %txid);
@@ -1286,12 +3024,199 @@ impl MemPoolDB {
         Ok(())
     }
 
-    #[cfg(test)]
-    pub fn dump_txs(&self) {
+    /// Feed `fee_estimator` the confirmation delay -- the number of blocks between admission and
+    /// confirmation -- for each of `txids`, which a newly-processed block confirmed at
+    /// `confirmed_height`.  Call this from the block-processing path before removing the
+    /// confirmed txs from the mempool (e.g. via `blocks_confirmed` or `drop_txs`), since it reads
+    /// each tx's admission height from its still-present mempool row; a txid no longer present
+    /// (already reaped by some other path) is silently skipped.
+    pub fn notify_confirmed_txs(
+        &self,
+        txids: &[Txid],
+        confirmed_height: u64,
+        fee_estimator: &mut dyn FeeEstimator,
+    ) -> Result<(), db_error> {
+        let mut confirmations = vec![];
+        for txid in txids.iter() {
+            if let Some(tx_info) = MemPoolDB::get_tx(self.conn(), txid)? {
+                let blocks_to_confirm =
+                    confirmed_height.saturating_sub(tx_info.metadata.block_height);
+                confirmations.push(ProcessedTxConfirmation {
+                    fee_rate: tx_info.metadata.fee_rate(),
+                    blocks_to_confirm,
+                });
+            }
+        }
+
+        if !confirmations.is_empty() {
+            fee_estimator.notify_confirmations(&confirmations)?;
+        }
+
+        Ok(())
+    }
+
+    /// Is this txid blacklisted?  A blacklisted txid must not be re-admitted to the mempool
+    /// (see `tx_submit`), and must not be offered up by `iterate_candidates`, until its
+    /// blacklist entry expires (see `garbage_collect_blacklist`).
+    pub fn is_txid_blacklisted(conn: &DBConn, txid: &Txid) -> Result<bool, db_error> {
+        let sql = "SELECT 1 FROM blacklisted_txids WHERE txid = ?1";
+        let args: &[&dyn ToSql] = &[txid];
+        let found: Option<i64> = query_row(conn, sql, args)?;
+        Ok(found.is_some())
+    }
+
+    /// Drop transactions from the mempool, and record them as blacklisted so that they can't
+    /// simply be resubmitted and re-walked by a miner.  Unlike `removed_txids`, the blacklist
+    /// isn't foreign-keyed to `mempool`, so an entry survives independently of whether its
+    /// mempool row still exists.
+    pub fn drop_and_blacklist_txs(
+        &mut self,
+        txids: &[Txid],
+        reason: MemPoolDropReason,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+    ) -> Result<(), db_error> {
+        let blacklist_time = get_epoch_time_secs();
+        let reason_str = reason.to_string();
+        let mempool_tx = self.tx_begin()?;
+
+        let drop_sql = "DELETE FROM mempool WHERE txid = ?1";
+        let blacklist_sql =
+            "INSERT OR REPLACE INTO blacklisted_txids (txid, reason, blacklist_time) VALUES (?1, ?2, ?3)";
+        for txid in txids.iter() {
+            mempool_tx.execute(drop_sql, &[txid])?;
+            let args: &[&dyn ToSql] = &[txid, &reason_str, &u64_to_sql(blacklist_time)?];
+            mempool_tx.execute(blacklist_sql, args)?;
+        }
+
+        mempool_tx.commit()?;
+
+        if let Some(event_observer) = event_observer {
+            event_observer.mempool_txs_dropped(txids.to_vec(), reason);
+        }
+
+        Ok(())
+    }
+
+    /// Remove blacklist entries older than `timeout` seconds, so that a txid which was only
+    /// transiently invalid eventually becomes admissible again.
+    pub fn garbage_collect_blacklist(tx: &mut MemPoolTx, timeout: u64) -> Result<(), db_error> {
+        let cutoff = get_epoch_time_secs().saturating_sub(timeout);
+        let sql = "DELETE FROM blacklisted_txids WHERE blacklist_time < ?1";
+        let args: &[&dyn ToSql] = &[&u64_to_sql(cutoff)?];
+        tx.execute(sql, args)?;
+        Ok(())
+    }
+
+    /// Serialize every transaction currently in the mempool to `path`, as a sequence of
+    /// length-prefixed records: a big-endian `u32` byte length, followed by that many bytes
+    /// consisting of the consensus hash and block header hash of the chain tip the transaction
+    /// was accepted at, its accept time, its fee, and its consensus-encoded bytes.  Pairs with
+    /// `load_txs`, and lets an operator snapshot the pending set across a chainstate migration
+    /// or share it with another node.
+    pub fn dump_txs(&self, path: &Path) -> Result<(), db_error> {
+        let mut fd = fs::File::create(path).map_err(|e| {
+            db_error::Other(format!("Failed to create {}: {:?}", path.display(), &e))
+        })?;
+
         let sql = "SELECT * FROM mempool";
-        let txs: Vec<MemPoolTxMetadata> = query_rows(&self.db, sql, NO_PARAMS).unwrap();
+        let txs: Vec<MemPoolTxInfo> = query_rows(&self.db, sql, NO_PARAMS)?;
+
+        for tx in txs.iter() {
+            let mut record = vec![];
+            record.extend_from_slice(&tx.metadata.consensus_hash.0);
+            record.extend_from_slice(&tx.metadata.block_header_hash.0);
+            record.extend_from_slice(&tx.metadata.accept_time.to_be_bytes());
+            record.extend_from_slice(&tx.metadata.tx_fee.to_be_bytes());
+            tx.tx
+                .consensus_serialize(&mut record)
+                .map_err(|e| db_error::Other(format!("Failed to serialize tx: {:?}", &e)))?;
+
+            let len = record.len() as u32;
+            fd.write_all(&len.to_be_bytes())
+                .and_then(|_| fd.write_all(&record))
+                .map_err(|e| {
+                    db_error::Other(format!("Failed to write {}: {:?}", path.display(), &e))
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Load transactions previously written by `dump_txs` from `path`, and re-admit each one
+    /// through the normal admission path (as if freshly broadcast at the chain tip it was
+    /// originally accepted at).  Transactions that are now stale, that conflict with what's
+    /// already in the mempool, or that chainstate no longer admits are simply skipped -- this is
+    /// meant for warm-starting a node's mempool, not for faithfully restoring every byte of a
+    /// previous run.
+    pub fn load_txs(
+        &mut self,
+        chainstate: &mut StacksChainState,
+        path: &Path,
+    ) -> Result<u64, db_error> {
+        let mut fd = fs::File::open(path).map_err(|e| {
+            db_error::Other(format!("Failed to open {}: {:?}", path.display(), &e))
+        })?;
+
+        let mut loaded = 0;
+        loop {
+            let mut len_bytes = [0u8; 4];
+            match fd.read_exact(&mut len_bytes) {
+                Ok(()) => {}
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => {
+                    return Err(db_error::Other(format!(
+                        "Failed to read {}: {:?}",
+                        path.display(),
+                        &e
+                    )))
+                }
+            };
+            let len = u32::from_be_bytes(len_bytes) as usize;
+
+            let mut record = vec![0u8; len];
+            fd.read_exact(&mut record).map_err(|e| {
+                db_error::Other(format!("Failed to read {}: {:?}", path.display(), &e))
+            })?;
+
+            if record.len() < 20 + 32 + 8 + 8 {
+                return Err(db_error::Other(format!(
+                    "Truncated mempool dump record in {}",
+                    path.display()
+                )));
+            }
+
+            let mut consensus_hash_bytes = [0u8; 20];
+            consensus_hash_bytes.copy_from_slice(&record[0..20]);
+            let consensus_hash = ConsensusHash(consensus_hash_bytes);
+
+            let mut block_header_hash_bytes = [0u8; 32];
+            block_header_hash_bytes.copy_from_slice(&record[20..52]);
+            let block_header_hash = BlockHeaderHash(block_header_hash_bytes);
+
+            let tx_bytes = &record[68..];
+            let tx = match StacksTransaction::consensus_deserialize(&mut &tx_bytes[..]) {
+                Ok(tx) => tx,
+                Err(e) => {
+                    warn!("Skipping unparseable transaction in {}: {:?}", path.display(), &e);
+                    continue;
+                }
+            };
+
+            match self.submit(chainstate, &consensus_hash, &block_header_hash, &tx, None) {
+                Ok(()) => {
+                    loaded += 1;
+                }
+                Err(e) => {
+                    debug!(
+                        "Not re-admitting transaction {} from {}: {:?}",
+                        &tx.txid(),
+                        path.display(),
+                        &e
+                    );
+                }
+            }
+        }
 
-        eprintln!("{:#?}", txs);
+        Ok(loaded)
     }
 
     /// Do we have a transaction?
@@ -1327,19 +3252,36 @@ impl MemPoolDB {
         }
     }
 
+    /// Find the maximum coinbase height represented in the mempool.  Unlike `get_max_height`,
+    /// this is fork-agnostic: it doesn't move backward across a sortition-level reorg that only
+    /// rewinds per-fork `height`.
+    fn get_max_coinbase_height(conn: &DBConn) -> Result<Option<u64>, db_error> {
+        let sql = "SELECT 1 FROM mempool WHERE coinbase_height >= 0";
+        let count = query_rows::<i64, _>(conn, sql, NO_PARAMS)?.len();
+        if count == 0 {
+            Ok(None)
+        } else {
+            let sql = "SELECT MAX(coinbase_height) FROM mempool";
+            Ok(Some(query_int(conn, sql, NO_PARAMS)? as u64))
+        }
+    }
+
     /// Get the transaction ID list that represents the set of transactions that are represented in
     /// the bloom counter.
     pub fn get_bloom_txids(&self) -> Result<Vec<Txid>, db_error> {
-        let max_height = match MemPoolDB::get_max_height(&self.conn())? {
+        let max_coinbase_height = match MemPoolDB::get_max_coinbase_height(&self.conn())? {
             Some(h) => h,
             None => {
                 // mempool is empty
                 return Ok(vec![]);
             }
         };
-        let min_height = max_height.saturating_sub(BLOOM_COUNTER_DEPTH as u64);
-        let sql = "SELECT mempool.txid FROM mempool WHERE height > ?1 AND height <= ?2 AND NOT EXISTS (SELECT 1 FROM removed_txids WHERE txid = mempool.txid)";
-        let args: &[&dyn ToSql] = &[&u64_to_sql(min_height)?, &u64_to_sql(max_height)?];
+        let min_coinbase_height = max_coinbase_height.saturating_sub(BLOOM_COUNTER_DEPTH as u64);
+        let sql = "SELECT mempool.txid FROM mempool WHERE coinbase_height > ?1 AND coinbase_height <= ?2 AND NOT EXISTS (SELECT 1 FROM removed_txids WHERE txid = mempool.txid)";
+        let args: &[&dyn ToSql] = &[
+            &u64_to_sql(min_coinbase_height)?,
+            &u64_to_sql(max_coinbase_height)?,
+        ];
         query_rows(&self.conn(), sql, args)
     }
 
@@ -1355,31 +3297,41 @@ impl MemPoolDB {
         })
     }
 
-    /// How many recent transactions are there -- i.e. within BLOOM_COUNTER_DEPTH block heights of
-    /// the chain tip?
+    /// How many recent transactions are there -- i.e. within BLOOM_COUNTER_DEPTH coinbase heights
+    /// of the chain tip?  Keying off of coinbase height (rather than the per-fork `height`) means
+    /// this doesn't double-count transactions that land at the same block height on competing
+    /// forks.
     pub fn get_num_recent_txs(conn: &DBConn) -> Result<u64, db_error> {
-        let max_height = match MemPoolDB::get_max_height(conn)? {
+        let max_coinbase_height = match MemPoolDB::get_max_coinbase_height(conn)? {
             Some(h) => h,
             None => {
                 // mempool is empty
                 return Ok(0);
             }
         };
-        let min_height = max_height.saturating_sub(BLOOM_COUNTER_DEPTH as u64);
-        let sql = "SELECT COUNT(txid) FROM mempool WHERE height > ?1 AND height <= ?2";
-        let args: &[&dyn ToSql] = &[&u64_to_sql(min_height)?, &u64_to_sql(max_height)?];
+        let min_coinbase_height = max_coinbase_height.saturating_sub(BLOOM_COUNTER_DEPTH as u64);
+        let sql = "SELECT COUNT(txid) FROM mempool WHERE coinbase_height > ?1 AND coinbase_height <= ?2";
+        let args: &[&dyn ToSql] = &[
+            &u64_to_sql(min_coinbase_height)?,
+            &u64_to_sql(max_coinbase_height)?,
+        ];
         query_int(conn, sql, args).map(|cnt| cnt as u64)
     }
 
     /// Make a mempool sync request.
-    /// If sufficiently sparse, use a MemPoolSyncData::TxTags variant
-    /// Otherwise, use a MemPoolSyncData::BloomFilter variant
+    /// If there are very few recent txids, use a MemPoolSyncData::Inventory variant -- an exact
+    /// list, with no false positives, that only costs more bytes per entry because there are few
+    /// entries to send.
+    /// Otherwise, if sufficiently sparse, use a MemPoolSyncData::TxTags variant.
+    /// Otherwise, use a MemPoolSyncData::BloomFilter variant.
     /// If force_bloom_filter is true, then always make a bloom filter.  The reason for doin this
     /// is that it's faster to do this than making a txtag list, even though the bloom filter is a
     /// larger data structure.
     pub fn make_mempool_sync_data(&self) -> Result<MemPoolSyncData, db_error> {
         let num_tags = MemPoolDB::get_num_recent_txs(self.conn())?;
-        if num_tags < self.max_tx_tags.into() {
+        if num_tags < self.max_inventory_txs.into() {
+            Ok(MemPoolSyncData::Inventory(self.get_bloom_txids()?))
+        } else if num_tags < self.max_tx_tags.into() {
             let seed = self.bloom_counter.get_seed().clone();
             let tags = self.get_txtags(&seed)?;
             Ok(MemPoolSyncData::TxTags(seed, tags))
@@ -1396,7 +3348,7 @@ impl MemPoolDB {
     pub fn find_next_missing_transactions(
         &self,
         data: &MemPoolSyncData,
-        height: u64,
+        coinbase_height: u64,
         last_txid: &Txid,
         max_txs: u64,
         max_run: u64,
@@ -1406,14 +3358,14 @@ impl MemPoolDB {
                    FROM mempool JOIN randomized_txids \
                    ON mempool.txid = randomized_txids.txid \
                    WHERE randomized_txids.hashed_txid > ?1 \
-                   AND mempool.height > ?2 \
+                   AND mempool.coinbase_height > ?2 \
                    AND NOT EXISTS \
                         (SELECT 1 FROM removed_txids WHERE txid = mempool.txid) \
                    ORDER BY randomized_txids.hashed_txid ASC LIMIT ?3";
 
         let args: &[&dyn ToSql] = &[
             &last_txid,
-            &u64_to_sql(height.saturating_sub(BLOOM_COUNTER_DEPTH as u64))?,
+            &u64_to_sql(coinbase_height.saturating_sub(BLOOM_COUNTER_DEPTH as u64))?,
             &u64_to_sql(max_run)?,
         ];
 
@@ -1423,6 +3375,12 @@ impl MemPoolDB {
                 tags_table.insert(tag.clone());
             }
         }
+        let mut inventory_table = HashSet::new();
+        if let MemPoolSyncData::Inventory(ref txids) = data {
+            for txid in txids.iter() {
+                inventory_table.insert(txid.clone());
+            }
+        }
 
         let mut stmt = self.conn().prepare(sql)?;
         let mut rows = stmt.query(args)?;
@@ -1434,6 +3392,7 @@ impl MemPoolDB {
                 MemPoolSyncData::TxTags(ref seed, ..) => {
                     tags_table.contains(&TxTag::from_seed_and_txid(seed, &txid))
                 }
+                MemPoolSyncData::Inventory(..) => inventory_table.contains(&txid),
             };
             if contains {
                 // remote peer already has this one
@@ -1454,12 +3413,178 @@ impl MemPoolDB {
         Ok(ret)
     }
 
-    /// Stream transaction data
-    pub fn stream_txs<W: Write>(
+    /// Get the next batch of transactions from our mempool that are *not* represented in the
+    /// given `MemPoolSyncData`, same as `find_next_missing_transactions`, but ordered by
+    /// descending estimated fee rate instead of `randomized_txids.hashed_txid`.  Meant for a
+    /// miner-facing consumer bootstrapping its own mempool, where getting the highest-value
+    /// transactions first matters more than the anti-censorship guarantees the randomized order
+    /// gives a gossiping peer.
+    ///
+    /// Since the ordering key here is fee rate rather than `hashed_txid`, the cursor a caller
+    /// threads across repeated calls must be the `(fee_rate, txid)` pair of the last transaction
+    /// returned, not just a txid -- `None` starts from the highest fee rate in the pool.
+    pub fn find_next_missing_transactions_by_fee_rate(
         &self,
-        fd: &mut W,
-        query: &mut TxStreamData,
-        count: u64,
+        data: &MemPoolSyncData,
+        coinbase_height: u64,
+        cursor: Option<(f64, Txid)>,
+        max_txs: u64,
+        max_run: u64,
+    ) -> Result<Vec<StacksTransaction>, db_error> {
+        let min_coinbase_height =
+            u64_to_sql(coinbase_height.saturating_sub(BLOOM_COUNTER_DEPTH as u64))?;
+        let run_limit = u64_to_sql(max_run)?;
+
+        let owned_rows: Vec<(Txid, Vec<u8>)> = match cursor {
+            Some((cursor_fee_rate, cursor_txid)) => {
+                let sql = "SELECT txid, tx FROM mempool \
+                           WHERE coinbase_height > ?1 \
+                           AND NOT EXISTS \
+                                (SELECT 1 FROM removed_txids WHERE txid = mempool.txid) \
+                           AND (\
+                               (CAST(tx_fee AS REAL) / estimated_cost) < ?2 \
+                               OR ((CAST(tx_fee AS REAL) / estimated_cost) = ?2 AND txid > ?3) \
+                           ) \
+                           ORDER BY (CAST(tx_fee AS REAL) / estimated_cost) DESC, txid ASC \
+                           LIMIT ?4";
+                let args: &[&dyn ToSql] =
+                    &[&min_coinbase_height, &cursor_fee_rate, &cursor_txid, &run_limit];
+                let mut stmt = self.conn().prepare(sql)?;
+                let mut rows = stmt.query(args)?;
+                let mut collected = vec![];
+                while let Some(row) = rows.next()? {
+                    let txid = Txid::from_column(row, "txid")?;
+                    let tx_bytes: Vec<u8> = row.get_unwrap("tx");
+                    collected.push((txid, tx_bytes));
+                }
+                collected
+            }
+            None => {
+                let sql = "SELECT txid, tx FROM mempool \
+                           WHERE coinbase_height > ?1 \
+                           AND NOT EXISTS \
+                                (SELECT 1 FROM removed_txids WHERE txid = mempool.txid) \
+                           ORDER BY (CAST(tx_fee AS REAL) / estimated_cost) DESC, txid ASC \
+                           LIMIT ?2";
+                let args: &[&dyn ToSql] = &[&min_coinbase_height, &run_limit];
+                let mut stmt = self.conn().prepare(sql)?;
+                let mut rows = stmt.query(args)?;
+                let mut collected = vec![];
+                while let Some(row) = rows.next()? {
+                    let txid = Txid::from_column(row, "txid")?;
+                    let tx_bytes: Vec<u8> = row.get_unwrap("tx");
+                    collected.push((txid, tx_bytes));
+                }
+                collected
+            }
+        };
+
+        MemPoolDB::filter_missing_txs(data, owned_rows, max_txs)
+    }
+
+    /// Diff this mempool's recent txids against a remote peer's `MemPoolSyncData`, returning
+    /// every local txid the peer's bloom filter/txtag set indicate it is missing.  Unlike
+    /// `find_next_missing_transactions` and `find_next_missing_transactions_by_fee_rate`, this
+    /// doesn't page through results or deserialize transaction bodies -- it's meant for a node
+    /// sizing up how much work a sync would take (or a test asserting two mempools converge),
+    /// not for building the wire response itself.  Because tag/bloom-filter membership has no
+    /// false negatives, every txid this returns is genuinely missing at the peer; a collision
+    /// can only cause this to under-report, never over-report, what the peer needs.
+    pub fn find_missing_txids(
+        &self,
+        data: &MemPoolSyncData,
+        coinbase_height: u64,
+    ) -> Result<Vec<Txid>, db_error> {
+        let min_coinbase_height =
+            u64_to_sql(coinbase_height.saturating_sub(BLOOM_COUNTER_DEPTH as u64))?;
+        let sql = "SELECT txid FROM mempool \
+                   WHERE coinbase_height > ?1 \
+                   AND NOT EXISTS \
+                        (SELECT 1 FROM removed_txids WHERE txid = mempool.txid)";
+        let args: &[&dyn ToSql] = &[&min_coinbase_height];
+        let local_txids: Vec<Txid> = query_rows(self.conn(), sql, args)?;
+
+        let mut tags_table = HashSet::new();
+        if let MemPoolSyncData::TxTags(_, ref tags) = data {
+            for tag in tags.iter() {
+                tags_table.insert(tag.clone());
+            }
+        }
+        let mut inventory_table = HashSet::new();
+        if let MemPoolSyncData::Inventory(ref txids) = data {
+            for txid in txids.iter() {
+                inventory_table.insert(txid.clone());
+            }
+        }
+
+        let missing = local_txids
+            .into_iter()
+            .filter(|txid| {
+                let contains = match data {
+                    MemPoolSyncData::BloomFilter(ref bf) => bf.contains_raw(&txid.0),
+                    MemPoolSyncData::TxTags(ref seed, ..) => {
+                        tags_table.contains(&TxTag::from_seed_and_txid(seed, txid))
+                    }
+                    MemPoolSyncData::Inventory(..) => inventory_table.contains(txid),
+                };
+                !contains
+            })
+            .collect();
+
+        Ok(missing)
+    }
+
+    /// Shared tail of `find_next_missing_transactions_by_fee_rate`: drop every row already
+    /// represented in `data`, deserialize the rest, and cap the result at `max_txs`.
+    fn filter_missing_txs(
+        data: &MemPoolSyncData,
+        rows: Vec<(Txid, Vec<u8>)>,
+        max_txs: u64,
+    ) -> Result<Vec<StacksTransaction>, db_error> {
+        let mut tags_table = HashSet::new();
+        if let MemPoolSyncData::TxTags(_, ref tags) = data {
+            for tag in tags.iter() {
+                tags_table.insert(tag.clone());
+            }
+        }
+        let mut inventory_table = HashSet::new();
+        if let MemPoolSyncData::Inventory(ref txids) = data {
+            for txid in txids.iter() {
+                inventory_table.insert(txid.clone());
+            }
+        }
+
+        let mut ret = vec![];
+        for (txid, tx_bytes) in rows.into_iter() {
+            let contains = match data {
+                MemPoolSyncData::BloomFilter(ref bf) => bf.contains_raw(&txid.0),
+                MemPoolSyncData::TxTags(ref seed, ..) => {
+                    tags_table.contains(&TxTag::from_seed_and_txid(seed, &txid))
+                }
+                MemPoolSyncData::Inventory(..) => inventory_table.contains(&txid),
+            };
+            if contains {
+                // remote peer already has this one
+                continue;
+            }
+
+            let tx = StacksTransaction::consensus_deserialize(&mut &tx_bytes[..])
+                .map_err(|_e| db_error::ParseError)?;
+            ret.push(tx);
+            if (ret.len() as u64) >= max_txs {
+                break;
+            }
+        }
+
+        Ok(ret)
+    }
+
+    /// Stream transaction data
+    pub fn stream_txs<W: Write>(
+        &self,
+        fd: &mut W,
+        query: &mut TxStreamData,
+        count: u64,
     ) -> Result<u64, ChainstateError> {
         let mut num_written = 0;
         while num_written < count {
@@ -1581,6 +3706,7 @@ mod tests {
     };
 
     use super::MemPoolDB;
+    use super::{FeeEstimator, ProcessedTxConfirmation, SQLiteFeeEstimator};
 
     use rand::prelude::*;
     use rand::thread_rng;
@@ -1819,7 +3945,8 @@ mod tests {
                         clarity_conn,
                         2,
                         mempool_settings.clone(),
-                        |_, available_tx| {
+                        &NullFeeEstimator,
+                        |_, available_tx, _fee_rate| {
                             count_txs += 1;
                             Ok(true)
                         },
@@ -1842,7 +3969,8 @@ mod tests {
                         clarity_conn,
                         3,
                         mempool_settings.clone(),
-                        |_, available_tx| {
+                        &NullFeeEstimator,
+                        |_, available_tx, _fee_rate| {
                             count_txs += 1;
                             Ok(true)
                         },
@@ -1865,7 +3993,8 @@ mod tests {
                         clarity_conn,
                         2,
                         mempool_settings.clone(),
-                        |_, available_tx| {
+                        &NullFeeEstimator,
+                        |_, available_tx, _fee_rate| {
                             count_txs += 1;
                             Ok(true)
                         },
@@ -1888,7 +4017,8 @@ mod tests {
                         clarity_conn,
                         3,
                         mempool_settings.clone(),
-                        |_, available_tx| {
+                        &NullFeeEstimator,
+                        |_, available_tx, _fee_rate| {
                             count_txs += 1;
                             Ok(true)
                         },
@@ -2012,17 +4142,14 @@ mod tests {
     }
 
     #[test]
-    fn mempool_do_not_replace_tx() {
+    fn mempool_walk_chained_nonces() {
         let mut chainstate = instantiate_chainstate_with_balances(
             false,
             0x80000000,
-            "mempool_do_not_replace_tx",
+            "mempool_walk_chained_nonces",
             vec![],
         );
 
-        // genesis -> b_1 -> b_2
-        //      \-> b_3
-        //
         let b_1 = make_block(
             &mut chainstate,
             ConsensusHash([0x1; 20]),
@@ -2033,501 +4160,2374 @@ mod tests {
             1,
             1,
         );
-        let b_2 = make_block(&mut chainstate, ConsensusHash([0x2; 20]), &b_1, 2, 2);
-        let b_3 = make_block(&mut chainstate, ConsensusHash([0x3; 20]), &b_1, 1, 1);
 
-        let chainstate_path = chainstate_path("mempool_do_not_replace_tx");
+        let chainstate_path = chainstate_path("mempool_walk_chained_nonces");
         let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
 
-        let mut txs = codec_all_transactions(
-            &TransactionVersion::Testnet,
-            0x80000000,
-            &TransactionAnchorMode::Any,
-            &TransactionPostConditionMode::Allow,
-        );
-        let mut tx = txs.pop().unwrap();
-
-        let mut mempool_tx = mempool.tx_begin().unwrap();
-
-        // do an initial insert
         let origin_address = StacksAddress {
             version: 22,
-            bytes: Hash160::from_data(&[0; 32]),
+            bytes: Hash160([0x11; 20]),
         };
         let sponsor_address = StacksAddress {
             version: 22,
-            bytes: Hash160::from_data(&[1; 32]),
-        };
-
-        tx.set_tx_fee(123);
-
-        // test insert
-        let txid = tx.txid();
-        let tx_bytes = tx.serialize_to_vec();
-
-        let tx_fee = tx.get_tx_fee();
-        let height = 100;
-
-        let origin_nonce = tx.get_origin_nonce();
-        let sponsor_nonce = match tx.get_sponsor_nonce() {
-            Some(n) => n,
-            None => origin_nonce,
+            bytes: Hash160([0x22; 20]),
         };
 
-        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
-
-        MemPoolDB::try_add_tx(
-            &mut mempool_tx,
-            &mut chainstate,
-            &b_1.0,
-            &b_1.1,
-            txid,
-            tx_bytes,
-            tx_fee,
-            height,
-            &origin_address,
-            origin_nonce,
-            &sponsor_address,
-            sponsor_nonce,
-            None,
-        )
-        .unwrap();
-
-        assert!(MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
-
-        let prior_txid = txid.clone();
-
-        // now, let's try inserting again, with a lower fee, but at a different block hash
-        tx.set_tx_fee(100);
-        let txid = tx.txid();
-        let tx_bytes = tx.serialize_to_vec();
-        let tx_fee = tx.get_tx_fee();
-        let height = 100;
+        // a 5-deep chain of nonces from the same origin, starting at the account's nonce (0),
+        // with fees that increase with depth so that fee-priority ordering alone would never
+        // pick these in nonce order.
+        {
+            let mut mempool_tx = mempool.tx_begin().unwrap();
+            for nonce in 0..5u64 {
+                let fee = 500 + (nonce * 10);
+                let tx = make_rbf_test_tx(nonce, fee);
+                MemPoolDB::try_add_tx(
+                    &mut mempool_tx,
+                    &mut chainstate,
+                    &b_1.0,
+                    &b_1.1,
+                    tx.txid(),
+                    tx.serialize_to_vec(),
+                    fee,
+                    1,
+                    &origin_address,
+                    nonce,
+                    &sponsor_address,
+                    nonce,
+                    None,
+                )
+                .unwrap();
+            }
+            mempool_tx.commit().unwrap();
+        }
 
-        let err_resp = MemPoolDB::try_add_tx(
-            &mut mempool_tx,
-            &mut chainstate,
-            &b_2.0,
-            &b_2.1,
-            txid,
-            tx_bytes,
-            tx_fee,
-            height,
-            &origin_address,
-            origin_nonce,
-            &sponsor_address,
-            sponsor_nonce,
-            None,
-        )
-        .unwrap_err();
-        assert!(match err_resp {
-            MemPoolRejection::ConflictingNonceInMempool => true,
-            _ => false,
-        });
+        let mempool_settings = MemPoolWalkSettings::default();
 
-        assert!(MemPoolDB::db_has_tx(&mempool_tx, &prior_txid).unwrap());
-        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+        chainstate.with_read_only_clarity_tx(
+            &NULL_BURN_STATE_DB,
+            &StacksBlockHeader::make_index_block_hash(&b_1.0, &b_1.1),
+            |clarity_conn| {
+                let mut observed_nonces = vec![];
+                let (count, stop_reason) = mempool
+                    .iterate_candidates::<_, ChainstateError, _>(
+                        clarity_conn,
+                        1,
+                        mempool_settings.clone(),
+                        &NullFeeEstimator,
+                        |_, available_tx, _fee_rate| {
+                            observed_nonces.push(available_tx.metadata.origin_nonce);
+                            Ok(true)
+                        },
+                    )
+                    .unwrap();
+                assert_eq!(count, 5, "All five chained transactions should be offered");
+                assert_eq!(
+                    observed_nonces,
+                    vec![0, 1, 2, 3, 4],
+                    "Chained transactions must be offered in contiguous nonce order"
+                );
+                assert_eq!(stop_reason, MempoolIterationStopReason::NoMoreCandidates);
+            },
+        );
     }
 
     #[test]
-    fn mempool_db_load_store_replace_tx() {
+    fn mempool_dump_and_load_txs() {
         let mut chainstate =
-            instantiate_chainstate(false, 0x80000000, "mempool_db_load_store_replace_tx");
-        let chainstate_path = chainstate_path("mempool_db_load_store_replace_tx");
+            instantiate_chainstate(false, 0x80000000, "mempool_dump_and_load_txs");
+
+        let chainstate_path = chainstate_path("mempool_dump_and_load_txs");
         let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
 
-        let mut txs = codec_all_transactions(
+        let mut all_txs = codec_all_transactions(
             &TransactionVersion::Testnet,
             0x80000000,
             &TransactionAnchorMode::Any,
             &TransactionPostConditionMode::Allow,
         );
-        let num_txs = txs.len() as u64;
+        let tx_a = all_txs.pop().unwrap();
+        let tx_b = all_txs.pop().unwrap();
 
-        let mut mempool_tx = mempool.tx_begin().unwrap();
+        // neither of these is a real chain tip -- make_block was never called for them -- so
+        // re-admitting against them on load should deterministically fail with NoSuchChainTip
+        // and be skipped, rather than resurrected into the destination mempool.
+        let stale_consensus_hash = ConsensusHash([0x9; 20]);
+        let stale_block_header_hash = BlockHeaderHash([0x9; 32]);
 
-        eprintln!("add all txs");
-        for (i, mut tx) in txs.drain(..).enumerate() {
-            // make sure each address is unique per tx (not the case in codec_all_transactions)
-            let origin_address = StacksAddress {
-                version: 22,
-                bytes: Hash160::from_data(&i.to_be_bytes()),
-            };
-            let sponsor_address = StacksAddress {
-                version: 22,
-                bytes: Hash160::from_data(&(i + 1).to_be_bytes()),
-            };
+        let addr_a = StacksAddress {
+            version: 22,
+            bytes: Hash160::from_data(&[0; 32]),
+        };
+        let addr_b = StacksAddress {
+            version: 22,
+            bytes: Hash160::from_data(&[1; 32]),
+        };
 
-            tx.set_tx_fee(123);
+        {
+            let mut mempool_tx = mempool.tx_begin().unwrap();
+            for (tx, addr) in [(&tx_a, &addr_a), (&tx_b, &addr_b)].iter() {
+                MemPoolDB::try_add_tx(
+                    &mut mempool_tx,
+                    &mut chainstate,
+                    &stale_consensus_hash,
+                    &stale_block_header_hash,
+                    tx.txid(),
+                    tx.serialize_to_vec(),
+                    tx.get_tx_fee(),
+                    1,
+                    addr,
+                    0,
+                    addr,
+                    0,
+                    None,
+                )
+                .unwrap();
+            }
+            mempool_tx.commit().unwrap();
+        }
 
-            // test insert
+        let dump_path = PathBuf::from(format!("{}.dump", &chainstate_path));
+        mempool.dump_txs(&dump_path).unwrap();
 
-            let txid = tx.txid();
-            let mut tx_bytes = vec![];
-            tx.consensus_serialize(&mut tx_bytes).unwrap();
-            let expected_tx = tx.clone();
+        let chainstate_path_2 = chainstate_path("mempool_dump_and_load_txs-dest");
+        let mut mempool_2 = MemPoolDB::open(false, 0x80000000, &chainstate_path_2).unwrap();
+        assert!(!MemPoolDB::db_has_tx(&mempool_2.db, &tx_a.txid()).unwrap());
+        assert!(!MemPoolDB::db_has_tx(&mempool_2.db, &tx_b.txid()).unwrap());
 
-            let tx_fee = tx.get_tx_fee();
-            let height = 100;
-            let origin_nonce = tx.get_origin_nonce();
-            let sponsor_nonce = match tx.get_sponsor_nonce() {
-                Some(n) => n,
-                None => origin_nonce,
-            };
-            let len = tx_bytes.len() as u64;
+        let loaded = mempool_2.load_txs(&mut chainstate, &dump_path).unwrap();
+        assert_eq!(
+            loaded, 0,
+            "Transactions anchored at a tip chainstate no longer recognizes must be skipped, not silently re-admitted"
+        );
+        assert!(!MemPoolDB::db_has_tx(&mempool_2.db, &tx_a.txid()).unwrap());
+        assert!(!MemPoolDB::db_has_tx(&mempool_2.db, &tx_b.txid()).unwrap());
 
-            assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+        fs::remove_file(&dump_path).unwrap();
+    }
 
-            MemPoolDB::try_add_tx(
-                &mut mempool_tx,
-                &mut chainstate,
-                &ConsensusHash([0x1; 20]),
-                &BlockHeaderHash([0x2; 32]),
-                txid,
-                tx_bytes,
-                tx_fee,
-                height,
-                &origin_address,
-                origin_nonce,
-                &sponsor_address,
-                sponsor_nonce,
-                None,
-            )
-            .unwrap();
+    #[test]
+    fn mempool_drop_and_blacklist_txs() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "mempool_drop_and_blacklist_txs");
 
-            assert!(MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
-
-            // test retrieval
-            let tx_info_opt = MemPoolDB::get_tx(&mempool_tx, &txid).unwrap();
-            let tx_info = tx_info_opt.unwrap();
-
-            assert_eq!(tx_info.tx, expected_tx);
-            assert_eq!(tx_info.metadata.len, len);
-            assert_eq!(tx_info.metadata.tx_fee, 123);
-            assert_eq!(tx_info.metadata.origin_address, origin_address);
-            assert_eq!(tx_info.metadata.origin_nonce, origin_nonce);
-            assert_eq!(tx_info.metadata.sponsor_address, sponsor_address);
-            assert_eq!(tx_info.metadata.sponsor_nonce, sponsor_nonce);
-            assert_eq!(tx_info.metadata.consensus_hash, ConsensusHash([0x1; 20]));
-            assert_eq!(
-                tx_info.metadata.block_header_hash,
-                BlockHeaderHash([0x2; 32])
-            );
-            assert_eq!(tx_info.metadata.block_height, height);
-
-            // test replace-by-fee with a higher fee
-            let old_txid = txid;
-
-            tx.set_tx_fee(124);
-            assert!(txid != tx.txid());
-
-            let txid = tx.txid();
-            let mut tx_bytes = vec![];
-            tx.consensus_serialize(&mut tx_bytes).unwrap();
-            let expected_tx = tx.clone();
-            let tx_fee = tx.get_tx_fee();
+        let b_1 = make_block(
+            &mut chainstate,
+            ConsensusHash([0x1; 20]),
+            &(
+                FIRST_BURNCHAIN_CONSENSUS_HASH.clone(),
+                FIRST_STACKS_BLOCK_HASH.clone(),
+            ),
+            1,
+            1,
+        );
 
-            assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+        let chainstate_path = chainstate_path("mempool_drop_and_blacklist_txs");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
 
-            let tx_info_before = MemPoolDB::get_tx_metadata_by_address(
-                &mempool_tx,
-                true,
-                &origin_address,
-                origin_nonce,
-            )
-            .unwrap()
-            .unwrap();
-            assert_eq!(tx_info_before, tx_info.metadata);
+        let origin_address = StacksAddress {
+            version: 22,
+            bytes: Hash160([0x11; 20]),
+        };
+        let tx = make_rbf_test_tx(0, 300);
 
+        {
+            let mut mempool_tx = mempool.tx_begin().unwrap();
             MemPoolDB::try_add_tx(
                 &mut mempool_tx,
                 &mut chainstate,
-                &ConsensusHash([0x1; 20]),
-                &BlockHeaderHash([0x2; 32]),
-                txid,
-                tx_bytes,
-                tx_fee,
-                height,
+                &b_1.0,
+                &b_1.1,
+                tx.txid(),
+                tx.serialize_to_vec(),
+                300,
+                1,
                 &origin_address,
-                origin_nonce,
-                &sponsor_address,
-                sponsor_nonce,
+                0,
+                &origin_address,
+                0,
                 None,
             )
             .unwrap();
+            mempool_tx.commit().unwrap();
+        }
 
-            // was replaced
-            assert!(!MemPoolDB::db_has_tx(&mempool_tx, &old_txid).unwrap());
-            assert!(MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+        assert!(MemPoolDB::db_has_tx(&mempool.db, &tx.txid()).unwrap());
+        assert!(!MemPoolDB::is_txid_blacklisted(&mempool.db, &tx.txid()).unwrap());
 
-            let tx_info_after = MemPoolDB::get_tx_metadata_by_address(
-                &mempool_tx,
-                true,
-                &origin_address,
-                origin_nonce,
-            )
-            .unwrap()
+        mempool
+            .drop_and_blacklist_txs(&[tx.txid()], MemPoolDropReason::TOO_EXPENSIVE, None)
             .unwrap();
-            assert!(tx_info_after != tx_info.metadata);
-
-            // test retrieval -- transaction should have been replaced because it has a higher
-            // estimated fee
-            let tx_info_opt = MemPoolDB::get_tx(&mempool_tx, &txid).unwrap();
-
-            let tx_info = tx_info_opt.unwrap();
-            assert_eq!(tx_info.metadata, tx_info_after);
-
-            assert_eq!(tx_info.tx, expected_tx);
-            assert_eq!(tx_info.metadata.len, len);
-            assert_eq!(tx_info.metadata.tx_fee, 124);
-            assert_eq!(tx_info.metadata.origin_address, origin_address);
-            assert_eq!(tx_info.metadata.origin_nonce, origin_nonce);
-            assert_eq!(tx_info.metadata.sponsor_address, sponsor_address);
-            assert_eq!(tx_info.metadata.sponsor_nonce, sponsor_nonce);
-            assert_eq!(tx_info.metadata.consensus_hash, ConsensusHash([0x1; 20]));
-            assert_eq!(
-                tx_info.metadata.block_header_hash,
-                BlockHeaderHash([0x2; 32])
-            );
-            assert_eq!(tx_info.metadata.block_height, height);
-
-            // test replace-by-fee with a lower fee
-            let old_txid = txid;
 
-            tx.set_tx_fee(122);
-            assert!(txid != tx.txid());
+        assert!(
+            !MemPoolDB::db_has_tx(&mempool.db, &tx.txid()).unwrap(),
+            "Blacklisting must delete the mempool row"
+        );
+        assert!(
+            MemPoolDB::is_txid_blacklisted(&mempool.db, &tx.txid()).unwrap(),
+            "Blacklisting must record the txid, independent of the (now-deleted) mempool row"
+        );
 
-            let txid = tx.txid();
-            let mut tx_bytes = vec![];
-            tx.consensus_serialize(&mut tx_bytes).unwrap();
-            let _expected_tx = tx.clone();
-            let tx_fee = tx.get_tx_fee();
+        // re-submitting the same transaction must be rejected outright, even though it would
+        // otherwise be perfectly admissible.
+        match mempool.submit_raw(&mut chainstate, &b_1.0, &b_1.1, tx.serialize_to_vec()) {
+            Err(MemPoolRejection::TxAlreadyBlacklisted(rejected_txid)) => {
+                assert_eq!(rejected_txid, tx.txid());
+            }
+            x => panic!("Expected TxAlreadyBlacklisted, got {:?}", &x),
+        }
 
-            assert!(match MemPoolDB::try_add_tx(
+        // a blacklisted transaction must not be offered up by `iterate_candidates` either,
+        // even if some other code path manages to get it back into the `mempool` table.
+        {
+            let mut mempool_tx = mempool.tx_begin().unwrap();
+            MemPoolDB::try_add_tx(
                 &mut mempool_tx,
                 &mut chainstate,
-                &ConsensusHash([0x1; 20]),
-                &BlockHeaderHash([0x2; 32]),
-                txid,
-                tx_bytes,
-                tx_fee,
-                height,
+                &b_1.0,
+                &b_1.1,
+                tx.txid(),
+                tx.serialize_to_vec(),
+                300,
+                1,
                 &origin_address,
-                origin_nonce,
-                &sponsor_address,
-                sponsor_nonce,
+                0,
+                &origin_address,
+                0,
                 None,
             )
-            .unwrap_err()
-            {
-                MemPoolRejection::ConflictingNonceInMempool => true,
-                _ => false,
-            });
-
-            // was NOT replaced
-            assert!(MemPoolDB::db_has_tx(&mempool_tx, &old_txid).unwrap());
-            assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+            .unwrap();
+            mempool_tx.commit().unwrap();
         }
-        mempool_tx.commit().unwrap();
-
-        eprintln!("get all txs");
-        let txs = MemPoolDB::get_txs_after(
-            &mempool.db,
-            &ConsensusHash([0x1; 20]),
-            &BlockHeaderHash([0x2; 32]),
-            0,
-            num_txs,
-        )
-        .unwrap();
-        assert_eq!(txs.len() as u64, num_txs);
-
-        eprintln!("get empty txs");
-        let txs = MemPoolDB::get_txs_after(
-            &mempool.db,
-            &ConsensusHash([0x1; 20]),
-            &BlockHeaderHash([0x3; 32]),
-            0,
-            num_txs,
-        )
-        .unwrap();
-        assert_eq!(txs.len(), 0);
-
-        eprintln!("get empty txs");
-        let txs = MemPoolDB::get_txs_after(
-            &mempool.db,
-            &ConsensusHash([0x2; 20]),
-            &BlockHeaderHash([0x2; 32]),
-            0,
-            num_txs,
-        )
-        .unwrap();
-        assert_eq!(txs.len(), 0);
-
-        eprintln!("garbage-collect");
-        let mut mempool_tx = mempool.tx_begin().unwrap();
-        MemPoolDB::garbage_collect(&mut mempool_tx, 101, None).unwrap();
-        mempool_tx.commit().unwrap();
 
-        let txs = MemPoolDB::get_txs_after(
-            &mempool.db,
-            &ConsensusHash([0x1; 20]),
-            &BlockHeaderHash([0x2; 32]),
-            0,
-            num_txs,
-        )
-        .unwrap();
-        assert_eq!(txs.len(), 0);
+        let mempool_settings = MemPoolWalkSettings::default();
+        chainstate.with_read_only_clarity_tx(
+            &NULL_BURN_STATE_DB,
+            &StacksBlockHeader::make_index_block_hash(&b_1.0, &b_1.1),
+            |clarity_conn| {
+                let (count, _) = mempool
+                    .iterate_candidates::<_, ChainstateError, _>(
+                        clarity_conn,
+                        1,
+                        mempool_settings.clone(),
+                        &NullFeeEstimator,
+                        |_, _available_tx, _fee_rate| Ok(true),
+                    )
+                    .unwrap();
+                assert_eq!(count, 0, "A blacklisted transaction must not be walked");
+            },
+        );
     }
 
     #[test]
-    fn mempool_db_test_rbf() {
-        let mut chainstate = instantiate_chainstate(false, 0x80000000, "mempool_db_test_rbf");
-        let chainstate_path = chainstate_path("mempool_db_test_rbf");
+    fn mempool_recent_rejects_cache() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "mempool_recent_rejects_cache");
+
+        let b_1 = make_block(
+            &mut chainstate,
+            ConsensusHash([0x1; 20]),
+            &(
+                FIRST_BURNCHAIN_CONSENSUS_HASH.clone(),
+                FIRST_STACKS_BLOCK_HASH.clone(),
+            ),
+            1,
+            1,
+        );
+        let b_2 = make_block(&mut chainstate, ConsensusHash([0x2; 20]), &b_1, 2, 2);
+
+        let chainstate_path = chainstate_path("mempool_recent_rejects_cache");
         let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
 
-        // create initial transaction
-        let mut mempool_tx = mempool.tx_begin().unwrap();
+        // tx_a and tx_b share the same signer (and so the same origin address/nonce), unlike
+        // `make_rbf_test_tx` which mints a fresh keypair every call -- `tx_submit`/`submit_raw`
+        // derive the conflicting address and nonce from the tx itself, so the two txs need to
+        // actually collide on both for this test to exercise the cache.
         let spending_condition =
             TransactionSpendingCondition::Singlesig(SinglesigSpendingCondition {
-                signer: Hash160([0x11; 20]),
+                signer: Hash160([0x21; 20]),
                 hash_mode: SinglesigHashMode::P2PKH,
                 key_encoding: TransactionPublicKeyEncoding::Uncompressed,
-                nonce: 123,
-                tx_fee: 456,
+                nonce: 0,
+                tx_fee: 300,
                 signature: MessageSignature::from_raw(&vec![0xff; 65]),
             });
         let stx_address = StacksAddress {
             version: 1,
-            bytes: Hash160([0xff; 20]),
+            bytes: Hash160([0xfe; 20]),
         };
-        let payload = TransactionPayload::TokenTransfer(
-            PrincipalData::from(QualifiedContractIdentifier {
-                issuer: stx_address.into(),
-                name: "hello-contract-name".into(),
-            }),
-            123,
-            TokenTransferMemo([0u8; 34]),
-        );
-        let mut tx = StacksTransaction {
+        let mut tx_a = StacksTransaction {
             version: TransactionVersion::Testnet,
             chain_id: 0x80000000,
             auth: TransactionAuth::Standard(spending_condition.clone()),
             anchor_mode: TransactionAnchorMode::Any,
             post_condition_mode: TransactionPostConditionMode::Allow,
             post_conditions: Vec::new(),
-            payload,
+            payload: TransactionPayload::TokenTransfer(
+                stx_address.clone().into(),
+                1,
+                TokenTransferMemo([0u8; 34]),
+            ),
         };
+        tx_a.set_tx_fee(300);
 
-        let i: usize = 0;
-        let origin_address = StacksAddress {
-            version: 22,
-            bytes: Hash160::from_data(&i.to_be_bytes()),
-        };
-        let sponsor_address = StacksAddress {
-            version: 22,
-            bytes: Hash160::from_data(&(i + 1).to_be_bytes()),
-        };
+        let mut tx_b = tx_a.clone();
+        tx_b.payload = TransactionPayload::TokenTransfer(
+            stx_address.into(),
+            2,
+            TokenTransferMemo([0u8; 34]),
+        );
+        // 301/300 is only a ~0.3% fee-rate bump -- short of the default 5% minimum.
+        tx_b.set_tx_fee(301);
+        assert!(tx_a.txid() != tx_b.txid());
 
-        tx.set_tx_fee(123);
-        let txid = tx.txid();
-        let mut tx_bytes = vec![];
-        tx.consensus_serialize(&mut tx_bytes).unwrap();
-        let expected_tx = tx.clone();
-        let tx_fee = tx.get_tx_fee();
-        let height = 100;
-        let origin_nonce = tx.get_origin_nonce();
-        let sponsor_nonce = match tx.get_sponsor_nonce() {
-            Some(n) => n,
-            None => origin_nonce,
-        };
-        let first_len = tx_bytes.len() as u64;
+        let origin_address = tx_a.origin_address();
 
-        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
-        MemPoolDB::try_add_tx(
-            &mut mempool_tx,
+        // occupy origin_address's nonce-0 slot with tx_a
+        {
+            let mut mempool_tx = mempool.tx_begin().unwrap();
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &b_1.0,
+                &b_1.1,
+                tx_a.txid(),
+                tx_a.serialize_to_vec(),
+                300,
+                1,
+                &origin_address,
+                0,
+                &origin_address,
+                0,
+                None,
+            )
+            .unwrap();
+            mempool_tx.commit().unwrap();
+        }
+
+        // tx_b collides with tx_a's nonce slot without a sufficient fee-rate bump -- this gets
+        // cached as a deterministic rejection against the b_1 tip.
+        match mempool.submit_raw(&mut chainstate, &b_1.0, &b_1.1, tx_b.serialize_to_vec()) {
+            Err(MemPoolRejection::ConflictingNonceInMempool) => (),
+            x => panic!("Expected ConflictingNonceInMempool, got {:?}", &x),
+        }
+
+        // remove tx_a out from underneath the cache, so that -- were the rejection not served
+        // from cache -- tx_b would now sail through admission with no conflict at all.
+        mempool.drop_txs(&[tx_a.txid()]).unwrap();
+
+        // still rejected: the cached answer for (b_1, tx_b) holds even though a fresh check
+        // against the current mempool contents would now admit it.
+        match mempool.submit_raw(&mut chainstate, &b_1.0, &b_1.1, tx_b.serialize_to_vec()) {
+            Err(MemPoolRejection::ConflictingNonceInMempool) => (),
+            x => panic!(
+                "Expected the cached ConflictingNonceInMempool rejection, got {:?}",
+                &x
+            ),
+        }
+
+        // submitting against a different tip invalidates the cache entirely, so tx_b is
+        // re-checked fresh and (with tx_a gone) is admitted.
+        mempool
+            .submit_raw(&mut chainstate, &b_2.0, &b_2.1, tx_b.serialize_to_vec())
+            .unwrap();
+        assert!(MemPoolDB::db_has_tx(&mempool.db, &tx_b.txid()).unwrap());
+    }
+
+    #[test]
+    fn mempool_pending_tx_store_and_promote() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "mempool_pending_tx_store_and_promote");
+
+        let b_1 = make_block(
             &mut chainstate,
-            &ConsensusHash([0x1; 20]),
-            &BlockHeaderHash([0x2; 32]),
-            txid,
-            tx_bytes,
-            tx_fee,
-            height,
-            &origin_address,
-            origin_nonce,
-            &sponsor_address,
-            sponsor_nonce,
-            None,
-        )
-        .unwrap();
-        assert!(MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+            ConsensusHash([0x1; 20]),
+            &(
+                FIRST_BURNCHAIN_CONSENSUS_HASH.clone(),
+                FIRST_STACKS_BLOCK_HASH.clone(),
+            ),
+            1,
+            1,
+        );
 
-        // test retrieval of initial transaction
-        let tx_info_opt = MemPoolDB::get_tx(&mempool_tx, &txid).unwrap();
-        let tx_info = tx_info_opt.unwrap();
+        let chainstate_path = chainstate_path("mempool_pending_tx_store_and_promote");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
 
-        // test replace-by-fee with a higher fee, where the payload is smaller
-        let old_txid = txid;
-        let old_tx_fee = tx_fee;
+        let origin_address = StacksAddress {
+            version: 22,
+            bytes: Hash160([0x11; 20]),
+        };
+        let tx = make_rbf_test_tx(1, 300);
+        let tx_bytes = tx.serialize_to_vec();
 
-        tx.set_tx_fee(124);
-        tx.payload = TransactionPayload::TokenTransfer(
-            stx_address.into(),
-            123,
-            TokenTransferMemo([0u8; 34]),
+        // buffer a tx for nonce 1, ahead of the account's confirmed nonce of 0.
+        {
+            let mut mempool_tx = mempool.tx_begin().unwrap();
+            MemPoolDB::store_pending_tx(
+                &mut mempool_tx,
+                &tx.txid(),
+                tx_bytes.clone(),
+                300,
+                tx_bytes.len() as u64,
+                &origin_address,
+                1,
+                &origin_address,
+                1,
+                &b_1.0,
+                &b_1.1,
+            )
+            .unwrap();
+            mempool_tx.commit().unwrap();
+        }
+
+        assert!(!MemPoolDB::db_has_tx(&mempool.db, &tx.txid()).unwrap());
+        assert!(MemPoolDB::has_pending_tx(&mempool.db, &origin_address, 1).unwrap());
+        assert_eq!(
+            MemPoolDB::get_next_nonce(&mempool.db, &origin_address, 0).unwrap(),
+            0,
+            "A tx buffered at nonce 1 must not change what nonce 0 looks like"
         );
-        assert!(txid != tx.txid());
-        let txid = tx.txid();
-        let mut tx_bytes = vec![];
-        tx.consensus_serialize(&mut tx_bytes).unwrap();
-        let expected_tx = tx.clone();
-        let tx_fee = tx.get_tx_fee();
-        let second_len = tx_bytes.len() as u64;
 
-        // these asserts are to ensure we are using the fee directly, not the fee rate
-        assert!(second_len < first_len);
-        assert!(second_len * tx_fee < first_len * old_tx_fee);
-        assert!(tx_fee > old_tx_fee);
-        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+        // promoting nonce 0 is a no-op: nothing is buffered there.
+        {
+            let mut mempool_tx = mempool.tx_begin().unwrap();
+            let promoted = MemPoolDB::promote_pending_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &origin_address,
+                0,
+                None,
+            )
+            .unwrap();
+            mempool_tx.commit().unwrap();
+            assert!(!promoted);
+        }
 
-        let tx_info_before =
-            MemPoolDB::get_tx_metadata_by_address(&mempool_tx, true, &origin_address, origin_nonce)
-                .unwrap()
+        // promoting nonce 1 moves the buffered tx into the mempool.
+        {
+            let mut mempool_tx = mempool.tx_begin().unwrap();
+            let promoted = MemPoolDB::promote_pending_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &origin_address,
+                1,
+                None,
+            )
+            .unwrap();
+            mempool_tx.commit().unwrap();
+            assert!(promoted, "Promoting nonce 1 must move the buffered tx into the mempool");
+        }
+
+        assert!(MemPoolDB::db_has_tx(&mempool.db, &tx.txid()).unwrap());
+        assert!(!MemPoolDB::has_pending_tx(&mempool.db, &origin_address, 1).unwrap());
+        assert_eq!(
+            MemPoolDB::get_next_nonce(&mempool.db, &origin_address, 1).unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn mempool_pending_tx_promotion_cascades_through_nonce_chain() {
+        let mut chainstate = instantiate_chainstate(
+            false,
+            0x80000000,
+            "mempool_pending_tx_promotion_cascades_through_nonce_chain",
+        );
+
+        let b_1 = make_block(
+            &mut chainstate,
+            ConsensusHash([0x1; 20]),
+            &(
+                FIRST_BURNCHAIN_CONSENSUS_HASH.clone(),
+                FIRST_STACKS_BLOCK_HASH.clone(),
+            ),
+            1,
+            1,
+        );
+
+        let chainstate_path =
+            chainstate_path("mempool_pending_tx_promotion_cascades_through_nonce_chain");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let origin_address = StacksAddress {
+            version: 22,
+            bytes: Hash160([0x22; 20]),
+        };
+
+        // buffer a 3-tx chain (nonces 5, 6, 7) out of order, all ahead of the account's
+        // confirmed nonce.
+        let txs: Vec<_> = (5..=7u64)
+            .map(|nonce| {
+                let tx = make_rbf_test_tx(nonce, 300);
+                let tx_bytes = tx.serialize_to_vec();
+                let mut mempool_tx = mempool.tx_begin().unwrap();
+                MemPoolDB::store_pending_tx(
+                    &mut mempool_tx,
+                    &tx.txid(),
+                    tx_bytes.clone(),
+                    300,
+                    tx_bytes.len() as u64,
+                    &origin_address,
+                    nonce,
+                    &origin_address,
+                    nonce,
+                    &b_1.0,
+                    &b_1.1,
+                )
                 .unwrap();
-        assert_eq!(tx_info_before, tx_info.metadata);
+                mempool_tx.commit().unwrap();
+                tx
+            })
+            .collect();
+
+        for (i, tx) in txs.iter().enumerate() {
+            let nonce = 5 + i as u64;
+            assert!(!MemPoolDB::db_has_tx(&mempool.db, &tx.txid()).unwrap());
+            assert!(MemPoolDB::has_pending_tx(&mempool.db, &origin_address, nonce).unwrap());
+        }
 
-        MemPoolDB::try_add_tx(
+        // promoting nonce 5 -- as if the account had just landed at nonce 4 -- must cascade
+        // through 6 and 7 too, since both were already the correct next nonces once 5 lands.
+        {
+            let mut mempool_tx = mempool.tx_begin().unwrap();
+            let promoted = MemPoolDB::promote_pending_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &origin_address,
+                5,
+                None,
+            )
+            .unwrap();
+            mempool_tx.commit().unwrap();
+            assert!(promoted);
+        }
+
+        for (i, tx) in txs.iter().enumerate() {
+            let nonce = 5 + i as u64;
+            assert!(
+                MemPoolDB::db_has_tx(&mempool.db, &tx.txid()).unwrap(),
+                "tx at nonce {} should have been promoted into the mempool",
+                nonce
+            );
+            assert!(!MemPoolDB::has_pending_tx(&mempool.db, &origin_address, nonce).unwrap());
+        }
+    }
+
+    #[test]
+    fn mempool_evicts_lowest_fee_rate_tx_for_space() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "mempool_evicts_lowest_fee_rate_tx_for_space");
+        let chainstate_path = chainstate_path("mempool_evicts_lowest_fee_rate_tx_for_space");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let consensus_hash = ConsensusHash([0x1; 20]);
+        let block_header_hash = BlockHeaderHash([0x2; 32]);
+
+        let addr = |b: u8| StacksAddress {
+            version: 22,
+            bytes: Hash160([b; 20]),
+        };
+
+        // each tx is the same size, so the one with the lowest absolute fee also has the
+        // lowest fee rate.
+        let tx_cheap = make_rbf_test_tx(1, 100);
+        let tx_expensive = make_rbf_test_tx(1, 1000);
+
+        let tx_cheap_bytes = tx_cheap.serialize_to_vec();
+        let tx_expensive_bytes = tx_expensive.serialize_to_vec();
+        assert_eq!(tx_cheap_bytes.len(), tx_expensive_bytes.len());
+
+        {
+            let mut mempool_tx = mempool.tx_begin().unwrap();
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &consensus_hash,
+                &block_header_hash,
+                tx_cheap.txid(),
+                tx_cheap_bytes,
+                100,
+                1,
+                &addr(1),
+                0,
+                &addr(1),
+                0,
+                None,
+            )
+            .unwrap();
+            mempool_tx.commit().unwrap();
+        }
+
+        assert!(MemPoolDB::db_has_tx(&mempool.db, &tx_cheap.txid()).unwrap());
+
+        // cap the mempool at just over one tx's worth of space, so admitting the second,
+        // higher-fee-rate tx must evict the first.
+        mempool
+            .admitter
+            .set_max_mempool_bytes(tx_expensive_bytes.len() as u64 + 10);
+
+        {
+            let mut mempool_tx = mempool.tx_begin().unwrap();
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &consensus_hash,
+                &block_header_hash,
+                tx_expensive.txid(),
+                tx_expensive_bytes,
+                1000,
+                1,
+                &addr(2),
+                0,
+                &addr(2),
+                0,
+                None,
+            )
+            .unwrap();
+            mempool_tx.commit().unwrap();
+        }
+
+        assert!(
+            !MemPoolDB::db_has_tx(&mempool.db, &tx_cheap.txid()).unwrap(),
+            "The lower fee-rate tx must be evicted to make room"
+        );
+        assert!(MemPoolDB::db_has_tx(&mempool.db, &tx_expensive.txid()).unwrap());
+        assert!(MemPoolDB::get_mempool_bytes(&mempool.db).unwrap() <= mempool.admitter.max_mempool_bytes);
+    }
+
+    #[test]
+    fn mempool_rejects_tx_that_would_evict_itself() {
+        let mut chainstate = instantiate_chainstate(
+            false,
+            0x80000000,
+            "mempool_rejects_tx_that_would_evict_itself",
+        );
+        let chainstate_path = chainstate_path("mempool_rejects_tx_that_would_evict_itself");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let consensus_hash = ConsensusHash([0x1; 20]);
+        let block_header_hash = BlockHeaderHash([0x2; 32]);
+
+        let addr = |b: u8| StacksAddress {
+            version: 22,
+            bytes: Hash160([b; 20]),
+        };
+
+        let tx = make_rbf_test_tx(1, 100);
+        let tx_bytes = tx.serialize_to_vec();
+
+        // cap the mempool below the size of this one tx, so admitting it would require
+        // evicting itself.
+        mempool
+            .admitter
+            .set_max_mempool_bytes(tx_bytes.len() as u64 - 1);
+
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        let res = MemPoolDB::try_add_tx(
             &mut mempool_tx,
             &mut chainstate,
-            &ConsensusHash([0x1; 20]),
-            &BlockHeaderHash([0x2; 32]),
-            txid,
+            &consensus_hash,
+            &block_header_hash,
+            tx.txid(),
             tx_bytes,
-            tx_fee,
-            height,
-            &origin_address,
-            origin_nonce,
-            &sponsor_address,
-            sponsor_nonce,
+            100,
+            1,
+            &addr(1),
+            0,
+            &addr(1),
+            0,
             None,
-        )
-        .unwrap();
+        );
+
+        match res {
+            Err(MemPoolRejection::Other(..)) => (),
+            Ok(..) => panic!("expected a tx that would evict itself to be rejected, but it was admitted"),
+            Err(..) => panic!("expected a tx that would evict itself to be rejected with MemPoolRejection::Other"),
+        }
+        mempool_tx.commit().unwrap();
+
+        assert!(!MemPoolDB::db_has_tx(&mempool.db, &tx.txid()).unwrap());
+    }
+
+    #[test]
+    fn mempool_do_not_replace_tx() {
+        let mut chainstate = instantiate_chainstate_with_balances(
+            false,
+            0x80000000,
+            "mempool_do_not_replace_tx",
+            vec![],
+        );
+
+        // genesis -> b_1 -> b_2
+        //      \-> b_3
+        //
+        let b_1 = make_block(
+            &mut chainstate,
+            ConsensusHash([0x1; 20]),
+            &(
+                FIRST_BURNCHAIN_CONSENSUS_HASH.clone(),
+                FIRST_STACKS_BLOCK_HASH.clone(),
+            ),
+            1,
+            1,
+        );
+        let b_2 = make_block(&mut chainstate, ConsensusHash([0x2; 20]), &b_1, 2, 2);
+        let b_3 = make_block(&mut chainstate, ConsensusHash([0x3; 20]), &b_1, 1, 1);
+
+        let chainstate_path = chainstate_path("mempool_do_not_replace_tx");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let mut txs = codec_all_transactions(
+            &TransactionVersion::Testnet,
+            0x80000000,
+            &TransactionAnchorMode::Any,
+            &TransactionPostConditionMode::Allow,
+        );
+        let mut tx = txs.pop().unwrap();
+
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+
+        // do an initial insert
+        let origin_address = StacksAddress {
+            version: 22,
+            bytes: Hash160::from_data(&[0; 32]),
+        };
+        let sponsor_address = StacksAddress {
+            version: 22,
+            bytes: Hash160::from_data(&[1; 32]),
+        };
+
+        tx.set_tx_fee(123);
+
+        // test insert
+        let txid = tx.txid();
+        let tx_bytes = tx.serialize_to_vec();
+
+        let tx_fee = tx.get_tx_fee();
+        let height = 100;
+
+        let origin_nonce = tx.get_origin_nonce();
+        let sponsor_nonce = match tx.get_sponsor_nonce() {
+            Some(n) => n,
+            None => origin_nonce,
+        };
+
+        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &b_1.0,
+            &b_1.1,
+            txid,
+            tx_bytes,
+            tx_fee,
+            height,
+            &origin_address,
+            origin_nonce,
+            &sponsor_address,
+            sponsor_nonce,
+            None,
+        )
+        .unwrap();
+
+        assert!(MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+
+        let prior_txid = txid.clone();
+
+        // now, let's try inserting again, with a lower fee, but at a different block hash
+        tx.set_tx_fee(100);
+        let txid = tx.txid();
+        let tx_bytes = tx.serialize_to_vec();
+        let tx_fee = tx.get_tx_fee();
+        let height = 100;
+
+        let err_resp = MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &b_2.0,
+            &b_2.1,
+            txid,
+            tx_bytes,
+            tx_fee,
+            height,
+            &origin_address,
+            origin_nonce,
+            &sponsor_address,
+            sponsor_nonce,
+            None,
+        )
+        .unwrap_err();
+        assert!(match err_resp {
+            MemPoolRejection::ConflictingNonceInMempool => true,
+            _ => false,
+        });
+
+        assert!(MemPoolDB::db_has_tx(&mempool_tx, &prior_txid).unwrap());
+        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+    }
+
+    #[test]
+    fn mempool_db_load_store_replace_tx() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "mempool_db_load_store_replace_tx");
+        let chainstate_path = chainstate_path("mempool_db_load_store_replace_tx");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let mut txs = codec_all_transactions(
+            &TransactionVersion::Testnet,
+            0x80000000,
+            &TransactionAnchorMode::Any,
+            &TransactionPostConditionMode::Allow,
+        );
+        let num_txs = txs.len() as u64;
+
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+
+        eprintln!("add all txs");
+        for (i, mut tx) in txs.drain(..).enumerate() {
+            // make sure each address is unique per tx (not the case in codec_all_transactions)
+            let origin_address = StacksAddress {
+                version: 22,
+                bytes: Hash160::from_data(&i.to_be_bytes()),
+            };
+            let sponsor_address = StacksAddress {
+                version: 22,
+                bytes: Hash160::from_data(&(i + 1).to_be_bytes()),
+            };
+
+            tx.set_tx_fee(123);
+
+            // test insert
+
+            let txid = tx.txid();
+            let mut tx_bytes = vec![];
+            tx.consensus_serialize(&mut tx_bytes).unwrap();
+            let expected_tx = tx.clone();
+
+            let tx_fee = tx.get_tx_fee();
+            let height = 100;
+            let origin_nonce = tx.get_origin_nonce();
+            let sponsor_nonce = match tx.get_sponsor_nonce() {
+                Some(n) => n,
+                None => origin_nonce,
+            };
+            let len = tx_bytes.len() as u64;
+
+            assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &ConsensusHash([0x1; 20]),
+                &BlockHeaderHash([0x2; 32]),
+                txid,
+                tx_bytes,
+                tx_fee,
+                height,
+                &origin_address,
+                origin_nonce,
+                &sponsor_address,
+                sponsor_nonce,
+                None,
+            )
+            .unwrap();
+
+            assert!(MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+
+            // test retrieval
+            let tx_info_opt = MemPoolDB::get_tx(&mempool_tx, &txid).unwrap();
+            let tx_info = tx_info_opt.unwrap();
+
+            assert_eq!(tx_info.tx, expected_tx);
+            assert_eq!(tx_info.metadata.len, len);
+            assert_eq!(tx_info.metadata.tx_fee, 123);
+            assert_eq!(tx_info.metadata.origin_address, origin_address);
+            assert_eq!(tx_info.metadata.origin_nonce, origin_nonce);
+            assert_eq!(tx_info.metadata.sponsor_address, sponsor_address);
+            assert_eq!(tx_info.metadata.sponsor_nonce, sponsor_nonce);
+            assert_eq!(tx_info.metadata.consensus_hash, ConsensusHash([0x1; 20]));
+            assert_eq!(
+                tx_info.metadata.block_header_hash,
+                BlockHeaderHash([0x2; 32])
+            );
+            assert_eq!(tx_info.metadata.block_height, height);
+
+            // test replace-by-fee with a higher fee
+            let old_txid = txid;
+
+            tx.set_tx_fee(130);
+            assert!(txid != tx.txid());
+
+            let txid = tx.txid();
+            let mut tx_bytes = vec![];
+            tx.consensus_serialize(&mut tx_bytes).unwrap();
+            let expected_tx = tx.clone();
+            let tx_fee = tx.get_tx_fee();
+
+            assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+
+            let tx_info_before = MemPoolDB::get_tx_metadata_by_address(
+                &mempool_tx,
+                true,
+                &origin_address,
+                origin_nonce,
+            )
+            .unwrap()
+            .unwrap();
+            assert_eq!(tx_info_before, tx_info.metadata);
+
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &ConsensusHash([0x1; 20]),
+                &BlockHeaderHash([0x2; 32]),
+                txid,
+                tx_bytes,
+                tx_fee,
+                height,
+                &origin_address,
+                origin_nonce,
+                &sponsor_address,
+                sponsor_nonce,
+                None,
+            )
+            .unwrap();
+
+            // was replaced
+            assert!(!MemPoolDB::db_has_tx(&mempool_tx, &old_txid).unwrap());
+            assert!(MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+
+            let tx_info_after = MemPoolDB::get_tx_metadata_by_address(
+                &mempool_tx,
+                true,
+                &origin_address,
+                origin_nonce,
+            )
+            .unwrap()
+            .unwrap();
+            assert!(tx_info_after != tx_info.metadata);
+
+            // test retrieval -- transaction should have been replaced because it has a higher
+            // estimated fee
+            let tx_info_opt = MemPoolDB::get_tx(&mempool_tx, &txid).unwrap();
+
+            let tx_info = tx_info_opt.unwrap();
+            assert_eq!(tx_info.metadata, tx_info_after);
+
+            assert_eq!(tx_info.tx, expected_tx);
+            assert_eq!(tx_info.metadata.len, len);
+            assert_eq!(tx_info.metadata.tx_fee, 130);
+            assert_eq!(tx_info.metadata.origin_address, origin_address);
+            assert_eq!(tx_info.metadata.origin_nonce, origin_nonce);
+            assert_eq!(tx_info.metadata.sponsor_address, sponsor_address);
+            assert_eq!(tx_info.metadata.sponsor_nonce, sponsor_nonce);
+            assert_eq!(tx_info.metadata.consensus_hash, ConsensusHash([0x1; 20]));
+            assert_eq!(
+                tx_info.metadata.block_header_hash,
+                BlockHeaderHash([0x2; 32])
+            );
+            assert_eq!(tx_info.metadata.block_height, height);
+
+            // test replace-by-fee with a lower fee
+            let old_txid = txid;
+
+            tx.set_tx_fee(122);
+            assert!(txid != tx.txid());
+
+            let txid = tx.txid();
+            let mut tx_bytes = vec![];
+            tx.consensus_serialize(&mut tx_bytes).unwrap();
+            let _expected_tx = tx.clone();
+            let tx_fee = tx.get_tx_fee();
+
+            assert!(match MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &ConsensusHash([0x1; 20]),
+                &BlockHeaderHash([0x2; 32]),
+                txid,
+                tx_bytes,
+                tx_fee,
+                height,
+                &origin_address,
+                origin_nonce,
+                &sponsor_address,
+                sponsor_nonce,
+                None,
+            )
+            .unwrap_err()
+            {
+                MemPoolRejection::ConflictingNonceInMempool => true,
+                _ => false,
+            });
+
+            // was NOT replaced
+            assert!(MemPoolDB::db_has_tx(&mempool_tx, &old_txid).unwrap());
+            assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+        }
+        mempool_tx.commit().unwrap();
+
+        eprintln!("get all txs");
+        let txs = MemPoolDB::get_txs_after(
+            &mempool.db,
+            &ConsensusHash([0x1; 20]),
+            &BlockHeaderHash([0x2; 32]),
+            0,
+            num_txs,
+        )
+        .unwrap();
+        assert_eq!(txs.len() as u64, num_txs);
+
+        eprintln!("get empty txs");
+        let txs = MemPoolDB::get_txs_after(
+            &mempool.db,
+            &ConsensusHash([0x1; 20]),
+            &BlockHeaderHash([0x3; 32]),
+            0,
+            num_txs,
+        )
+        .unwrap();
+        assert_eq!(txs.len(), 0);
+
+        eprintln!("get empty txs");
+        let txs = MemPoolDB::get_txs_after(
+            &mempool.db,
+            &ConsensusHash([0x2; 20]),
+            &BlockHeaderHash([0x2; 32]),
+            0,
+            num_txs,
+        )
+        .unwrap();
+        assert_eq!(txs.len(), 0);
+
+        eprintln!("garbage-collect");
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        MemPoolDB::garbage_collect(&mut mempool_tx, 101, None).unwrap();
+        mempool_tx.commit().unwrap();
+
+        let txs = MemPoolDB::get_txs_after(
+            &mempool.db,
+            &ConsensusHash([0x1; 20]),
+            &BlockHeaderHash([0x2; 32]),
+            0,
+            num_txs,
+        )
+        .unwrap();
+        assert_eq!(txs.len(), 0);
+    }
+
+    #[test]
+    fn mempool_db_test_rbf() {
+        let mut chainstate = instantiate_chainstate(false, 0x80000000, "mempool_db_test_rbf");
+        let chainstate_path = chainstate_path("mempool_db_test_rbf");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        // create initial transaction
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        let spending_condition =
+            TransactionSpendingCondition::Singlesig(SinglesigSpendingCondition {
+                signer: Hash160([0x11; 20]),
+                hash_mode: SinglesigHashMode::P2PKH,
+                key_encoding: TransactionPublicKeyEncoding::Uncompressed,
+                nonce: 123,
+                tx_fee: 456,
+                signature: MessageSignature::from_raw(&vec![0xff; 65]),
+            });
+        let stx_address = StacksAddress {
+            version: 1,
+            bytes: Hash160([0xff; 20]),
+        };
+        let payload = TransactionPayload::TokenTransfer(
+            PrincipalData::from(QualifiedContractIdentifier {
+                issuer: stx_address.into(),
+                name: "hello-contract-name".into(),
+            }),
+            123,
+            TokenTransferMemo([0u8; 34]),
+        );
+        let mut tx = StacksTransaction {
+            version: TransactionVersion::Testnet,
+            chain_id: 0x80000000,
+            auth: TransactionAuth::Standard(spending_condition.clone()),
+            anchor_mode: TransactionAnchorMode::Any,
+            post_condition_mode: TransactionPostConditionMode::Allow,
+            post_conditions: Vec::new(),
+            payload,
+        };
+
+        let i: usize = 0;
+        let origin_address = StacksAddress {
+            version: 22,
+            bytes: Hash160::from_data(&i.to_be_bytes()),
+        };
+        let sponsor_address = StacksAddress {
+            version: 22,
+            bytes: Hash160::from_data(&(i + 1).to_be_bytes()),
+        };
+
+        tx.set_tx_fee(123);
+        let txid = tx.txid();
+        let mut tx_bytes = vec![];
+        tx.consensus_serialize(&mut tx_bytes).unwrap();
+        let expected_tx = tx.clone();
+        let tx_fee = tx.get_tx_fee();
+        let height = 100;
+        let origin_nonce = tx.get_origin_nonce();
+        let sponsor_nonce = match tx.get_sponsor_nonce() {
+            Some(n) => n,
+            None => origin_nonce,
+        };
+        let first_len = tx_bytes.len() as u64;
+
+        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &ConsensusHash([0x1; 20]),
+            &BlockHeaderHash([0x2; 32]),
+            txid,
+            tx_bytes,
+            tx_fee,
+            height,
+            &origin_address,
+            origin_nonce,
+            &sponsor_address,
+            sponsor_nonce,
+            None,
+        )
+        .unwrap();
+        assert!(MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+
+        // test retrieval of initial transaction
+        let tx_info_opt = MemPoolDB::get_tx(&mempool_tx, &txid).unwrap();
+        let tx_info = tx_info_opt.unwrap();
+
+        // a replacement with a slightly higher absolute fee, but a smaller payload, that ends
+        // up with a *lower* fee rate than the incumbent must now be rejected.
+        let old_txid = txid;
+        let old_tx_fee = tx_fee;
+
+        tx.set_tx_fee(124);
+        tx.payload = TransactionPayload::TokenTransfer(
+            stx_address.into(),
+            123,
+            TokenTransferMemo([0u8; 34]),
+        );
+        assert!(txid != tx.txid());
+        let txid = tx.txid();
+        let mut tx_bytes = vec![];
+        tx.consensus_serialize(&mut tx_bytes).unwrap();
+        let tx_fee = tx.get_tx_fee();
+        let second_len = tx_bytes.len() as u64;
+
+        // these asserts confirm the replacement has a higher absolute fee but a lower fee rate
+        assert!(second_len < first_len);
+        assert!(second_len * tx_fee < first_len * old_tx_fee);
+        assert!(tx_fee > old_tx_fee);
+        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+
+        let tx_info_before =
+            MemPoolDB::get_tx_metadata_by_address(&mempool_tx, true, &origin_address, origin_nonce)
+                .unwrap()
+                .unwrap();
+        assert_eq!(tx_info_before, tx_info.metadata);
+
+        let err_resp = MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &ConsensusHash([0x1; 20]),
+            &BlockHeaderHash([0x2; 32]),
+            txid,
+            tx_bytes,
+            tx_fee,
+            height,
+            &origin_address,
+            origin_nonce,
+            &sponsor_address,
+            sponsor_nonce,
+            None,
+        )
+        .unwrap_err();
+        assert!(match err_resp {
+            MemPoolRejection::ConflictingNonceInMempool => true,
+            _ => false,
+        });
+
+        // the incumbent must still be in place -- a higher absolute fee is not enough on its own
+        assert!(MemPoolDB::db_has_tx(&mempool_tx, &old_txid).unwrap());
+        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+
+        // bumping the fee rate by comfortably more than the default minimum, though, must
+        // succeed even though the payload is still smaller than the original.
+        tx.set_tx_fee(old_tx_fee * 10);
+        let txid = tx.txid();
+        let mut tx_bytes = vec![];
+        tx.consensus_serialize(&mut tx_bytes).unwrap();
+        let tx_fee = tx.get_tx_fee();
+
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &ConsensusHash([0x1; 20]),
+            &BlockHeaderHash([0x2; 32]),
+            txid,
+            tx_bytes,
+            tx_fee,
+            height,
+            &origin_address,
+            origin_nonce,
+            &sponsor_address,
+            sponsor_nonce,
+            None,
+        )
+        .unwrap();
+
+        // check that the transaction was replaced
+        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &old_txid).unwrap());
+        assert!(MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+
+        let tx_info_after =
+            MemPoolDB::get_tx_metadata_by_address(&mempool_tx, true, &origin_address, origin_nonce)
+                .unwrap()
+                .unwrap();
+        assert!(tx_info_after != tx_info.metadata);
+
+        // test retrieval -- transaction should have been replaced because its fee rate cleared
+        // the minimum required bump
+        let tx_info_opt = MemPoolDB::get_tx(&mempool_tx, &txid).unwrap();
+        let tx_info = tx_info_opt.unwrap();
+        assert_eq!(tx_info.metadata, tx_info_after);
+        assert_eq!(tx_info.metadata.tx_fee, old_tx_fee * 10);
+        assert_eq!(tx_info.metadata.len, second_len);
+    }
+
+    #[test]
+    fn mempool_db_test_rbf_fee_rate_increment() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "mempool_db_test_rbf_fee_rate_increment");
+        let chainstate_path = chainstate_path("mempool_db_test_rbf_fee_rate_increment");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let increment = 2;
+        mempool
+            .admitter
+            .set_rbf_requirement(RbfRequirement::FeeRateIncrement);
+        mempool.admitter.set_min_rbf_fee_rate_increment(increment);
+
+        let consensus_hash = ConsensusHash([0x1; 20]);
+        let block_header_hash = BlockHeaderHash([0x2; 32]);
+        let addr = StacksAddress {
+            version: 22,
+            bytes: Hash160([0x1; 20]),
+        };
+
+        let old_tx_fee = 100;
+        let tx_old = make_rbf_test_tx(1, old_tx_fee);
+        let len = tx_old.serialize_to_vec().len() as u64;
+
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &consensus_hash,
+            &block_header_hash,
+            tx_old.txid(),
+            tx_old.serialize_to_vec(),
+            old_tx_fee,
+            1,
+            &addr,
+            0,
+            &addr,
+            0,
+            None,
+        )
+        .unwrap();
+
+        // replacement txs below are built with the same payload shape as `tx_old`, so they share
+        // its serialized length -- the required new fee to clear the increment is then exactly
+        // `old_tx_fee + increment * len`.
+
+        // one satoshi short of the required increment: must be rejected, and the incumbent
+        // must remain in place.
+        let tx_low = make_rbf_test_tx(2, old_tx_fee + increment * len - 1);
+        assert!(MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &consensus_hash,
+            &block_header_hash,
+            tx_low.txid(),
+            tx_low.serialize_to_vec(),
+            old_tx_fee + increment * len - 1,
+            1,
+            &addr,
+            0,
+            &addr,
+            0,
+            None,
+        )
+        .is_err());
+        assert!(MemPoolDB::db_has_tx(&mempool_tx, &tx_old.txid()).unwrap());
+
+        // exactly the required increment: must be admitted, replacing the incumbent.
+        let tx_high = make_rbf_test_tx(3, old_tx_fee + increment * len);
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &consensus_hash,
+            &block_header_hash,
+            tx_high.txid(),
+            tx_high.serialize_to_vec(),
+            old_tx_fee + increment * len,
+            1,
+            &addr,
+            0,
+            &addr,
+            0,
+            None,
+        )
+        .unwrap();
+        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &tx_old.txid()).unwrap());
+        assert!(MemPoolDB::db_has_tx(&mempool_tx, &tx_high.txid()).unwrap());
+    }
+
+    /// Build a minimal signed TokenTransfer tx for the nonce-conflict RBF tests below.  The
+    /// `tag` only needs to make the resulting txid unique.
+    fn make_rbf_test_tx(tag: u64, tx_fee: u64) -> StacksTransaction {
+        let pk = StacksPrivateKey::new();
+        let mut tx = StacksTransaction {
+            version: TransactionVersion::Testnet,
+            chain_id: 0x80000000,
+            auth: TransactionAuth::from_p2pkh(&pk).unwrap(),
+            anchor_mode: TransactionAnchorMode::Any,
+            post_condition_mode: TransactionPostConditionMode::Allow,
+            post_conditions: vec![],
+            payload: TransactionPayload::TokenTransfer(
+                StacksAddress {
+                    version: 1,
+                    bytes: Hash160([0xfe; 20]),
+                }
+                .to_account_principal(),
+                tag,
+                TokenTransferMemo([0u8; 34]),
+            ),
+        };
+        tx.set_tx_fee(tx_fee);
+        tx
+    }
+
+    #[test]
+    fn mempool_db_test_rbf_nonce_conflicts() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "mempool_db_test_rbf_nonce_conflicts");
+        let chainstate_path = chainstate_path("mempool_db_test_rbf_nonce_conflicts");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+
+        let consensus_hash = ConsensusHash([0x1; 20]);
+        let block_header_hash = BlockHeaderHash([0x2; 32]);
+
+        let addr = |b: u8| StacksAddress {
+            version: 22,
+            bytes: Hash160([b; 20]),
+        };
+
+        // --- origin-only collision ---
+        let alice = addr(1);
+        let bob = addr(2);
+        let carol = addr(3);
+
+        let tx_a = make_rbf_test_tx(1, 100);
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &consensus_hash,
+            &block_header_hash,
+            tx_a.txid(),
+            tx_a.serialize_to_vec(),
+            100,
+            1,
+            &alice,
+            5,
+            &bob,
+            5,
+            None,
+        )
+        .unwrap();
+
+        // same origin-nonce slot, different sponsor -- insufficient fee to replace
+        let tx_b_low = make_rbf_test_tx(2, 50);
+        assert!(MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &consensus_hash,
+            &block_header_hash,
+            tx_b_low.txid(),
+            tx_b_low.serialize_to_vec(),
+            50,
+            1,
+            &alice,
+            5,
+            &carol,
+            1,
+            None,
+        )
+        .is_err());
+        assert!(MemPoolDB::db_has_tx(&mempool_tx, &tx_a.txid()).unwrap());
+
+        // same origin-nonce slot, different sponsor -- sufficient fee to replace
+        let tx_b_high = make_rbf_test_tx(3, 150);
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &consensus_hash,
+            &block_header_hash,
+            tx_b_high.txid(),
+            tx_b_high.serialize_to_vec(),
+            150,
+            1,
+            &alice,
+            5,
+            &carol,
+            1,
+            None,
+        )
+        .unwrap();
+        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &tx_a.txid()).unwrap());
+        assert!(MemPoolDB::db_has_tx(&mempool_tx, &tx_b_high.txid()).unwrap());
+
+        // --- sponsor-only collision ---
+        let dave = addr(4);
+        let eve = addr(5);
+        let shared_sponsor = addr(6);
+
+        let tx_d = make_rbf_test_tx(4, 100);
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &consensus_hash,
+            &block_header_hash,
+            tx_d.txid(),
+            tx_d.serialize_to_vec(),
+            100,
+            1,
+            &dave,
+            9,
+            &shared_sponsor,
+            20,
+            None,
+        )
+        .unwrap();
+
+        // same sponsor-nonce slot, different origin -- insufficient fee to replace
+        let tx_e_low = make_rbf_test_tx(5, 50);
+        assert!(MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &consensus_hash,
+            &block_header_hash,
+            tx_e_low.txid(),
+            tx_e_low.serialize_to_vec(),
+            50,
+            1,
+            &eve,
+            1,
+            &shared_sponsor,
+            20,
+            None,
+        )
+        .is_err());
+        assert!(MemPoolDB::db_has_tx(&mempool_tx, &tx_d.txid()).unwrap());
+
+        // same sponsor-nonce slot, different origin -- sufficient fee to replace
+        let tx_e_high = make_rbf_test_tx(6, 150);
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &consensus_hash,
+            &block_header_hash,
+            tx_e_high.txid(),
+            tx_e_high.serialize_to_vec(),
+            150,
+            1,
+            &eve,
+            1,
+            &shared_sponsor,
+            20,
+            None,
+        )
+        .unwrap();
+        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &tx_d.txid()).unwrap());
+        assert!(MemPoolDB::db_has_tx(&mempool_tx, &tx_e_high.txid()).unwrap());
+
+        // --- combined collision: one incoming tx displaces two distinct existing txs ---
+        let frank = addr(7);
+        let grace = addr(8);
+        let heidi = addr(9);
+        let ivan = addr(10);
+
+        let tx_f = make_rbf_test_tx(7, 100);
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &consensus_hash,
+            &block_header_hash,
+            tx_f.txid(),
+            tx_f.serialize_to_vec(),
+            100,
+            1,
+            &frank,
+            2,
+            &grace,
+            3,
+            None,
+        )
+        .unwrap();
+
+        let tx_g = make_rbf_test_tx(8, 80);
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &consensus_hash,
+            &block_header_hash,
+            tx_g.txid(),
+            tx_g.serialize_to_vec(),
+            80,
+            1,
+            &heidi,
+            4,
+            &ivan,
+            5,
+            None,
+        )
+        .unwrap();
+
+        // collides with tx_f via origin (frank,2) and tx_g via sponsor (ivan,5) -- all these
+        // txs share the same serialized length, so beating tx_f's fee rate is the binding
+        // constraint (100 * 1.05 = 105); 104 clears tx_g's bar but not tx_f's, and the new tx
+        // must beat *every* same-fork conflict's rate by the minimum bump to be admitted
+        let tx_h_low = make_rbf_test_tx(9, 104);
+        assert!(MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &consensus_hash,
+            &block_header_hash,
+            tx_h_low.txid(),
+            tx_h_low.serialize_to_vec(),
+            104,
+            1,
+            &frank,
+            2,
+            &ivan,
+            5,
+            None,
+        )
+        .is_err());
+        assert!(MemPoolDB::db_has_tx(&mempool_tx, &tx_f.txid()).unwrap());
+        assert!(MemPoolDB::db_has_tx(&mempool_tx, &tx_g.txid()).unwrap());
+
+        // same collision, but this time the fee rate clears the minimum bump over both
+        // conflicting txs, so both get replaced by the single new tx
+        let tx_h_high = make_rbf_test_tx(10, 110);
+        let evicted = MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &consensus_hash,
+            &block_header_hash,
+            tx_h_high.txid(),
+            tx_h_high.serialize_to_vec(),
+            110,
+            1,
+            &frank,
+            2,
+            &ivan,
+            5,
+            None,
+        )
+        .unwrap();
+        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &tx_f.txid()).unwrap());
+        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &tx_g.txid()).unwrap());
+        assert!(MemPoolDB::db_has_tx(&mempool_tx, &tx_h_high.txid()).unwrap());
+        assert_eq!(evicted.len(), 2);
+        assert!(evicted.contains(&tx_f.txid()));
+        assert!(evicted.contains(&tx_g.txid()));
+    }
+
+    #[test]
+    fn mempool_db_test_rbf_replacement_evicted_from_sync_data() {
+        let mut chainstate = instantiate_chainstate(
+            false,
+            0x80000000,
+            "mempool_db_test_rbf_replacement_evicted_from_sync_data",
+        );
+        let chainstate_path =
+            chainstate_path("mempool_db_test_rbf_replacement_evicted_from_sync_data");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let consensus_hash = ConsensusHash([0x1; 20]);
+        let block_header_hash = BlockHeaderHash([0x2; 32]);
+        let addr = StacksAddress {
+            version: 22,
+            bytes: Hash160([0x1; 20]),
+        };
+
+        let tx_old = make_rbf_test_tx(1, 100);
+        {
+            let mut mempool_tx = mempool.tx_begin().unwrap();
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &consensus_hash,
+                &block_header_hash,
+                tx_old.txid(),
+                tx_old.serialize_to_vec(),
+                100,
+                1,
+                &addr,
+                0,
+                &addr,
+                0,
+                None,
+            )
+            .unwrap();
+            mempool_tx.commit().unwrap();
+        }
+
+        let seed = [0u8; 32];
+        let bf = mempool.get_txid_bloom_filter().unwrap();
+        assert!(bf.contains_raw(&tx_old.txid().0));
+        let txtags = mempool.get_txtags(&seed).unwrap();
+        assert!(txtags.contains(&TxTag::from_seed_and_txid(&seed, &tx_old.txid())));
+
+        // same origin-nonce slot, sufficient fee to replace
+        let tx_new = make_rbf_test_tx(2, 150);
+        let evicted = {
+            let mut mempool_tx = mempool.tx_begin().unwrap();
+            let evicted = MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &consensus_hash,
+                &block_header_hash,
+                tx_new.txid(),
+                tx_new.serialize_to_vec(),
+                150,
+                1,
+                &addr,
+                0,
+                &addr,
+                0,
+                None,
+            )
+            .unwrap();
+            mempool_tx.commit().unwrap();
+            evicted
+        };
+        assert_eq!(evicted, vec![tx_old.txid()]);
+
+        let bf = mempool.get_txid_bloom_filter().unwrap();
+        assert!(
+            !bf.contains_raw(&tx_old.txid().0),
+            "replaced tx should no longer be counted in the sync bloom filter"
+        );
+        assert!(bf.contains_raw(&tx_new.txid().0));
+
+        let txtags = mempool.get_txtags(&seed).unwrap();
+        assert!(
+            !txtags.contains(&TxTag::from_seed_and_txid(&seed, &tx_old.txid())),
+            "replaced tx should no longer appear in the txtag sync set"
+        );
+        assert!(txtags.contains(&TxTag::from_seed_and_txid(&seed, &tx_new.txid())));
+    }
+
+    #[test]
+    fn mempool_db_test_next_mineable_nonce() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "mempool_db_test_next_mineable_nonce");
+        let chainstate_path = chainstate_path("mempool_db_test_next_mineable_nonce");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+
+        let consensus_hash = ConsensusHash([0x1; 20]);
+        let block_header_hash = BlockHeaderHash([0x2; 32]);
+        let sponsor = StacksAddress {
+            version: 22,
+            bytes: Hash160([0xaa; 20]),
+        };
+        let origin = StacksAddress {
+            version: 22,
+            bytes: Hash160([0xbb; 20]),
+        };
+
+        // nothing in the mempool yet -- the next mineable nonce is just the account nonce
+        assert_eq!(
+            MemPoolDB::get_next_mineable_nonce(&mempool_tx, &origin, 5).unwrap(),
+            5
+        );
+
+        // fill in a contiguous run of nonces 5, 6, 7
+        for (tag, nonce) in [(1u64, 5u64), (2, 6), (3, 7)].iter() {
+            let tx = make_rbf_test_tx(*tag, 100);
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &consensus_hash,
+                &block_header_hash,
+                tx.txid(),
+                tx.serialize_to_vec(),
+                100,
+                1,
+                &origin,
+                *nonce,
+                &sponsor,
+                *nonce,
+                None,
+            )
+            .unwrap();
+        }
+
+        // the run is contiguous from the account nonce, so the next mineable nonce is one past it
+        assert_eq!(
+            MemPoolDB::get_next_mineable_nonce(&mempool_tx, &origin, 5).unwrap(),
+            8
+        );
+
+        // leave a gap at nonce 9 -- add nonce 10 instead.  the run from the account nonce is
+        // unaffected by a transaction that isn't part of it.
+        let tx_gap = make_rbf_test_tx(4, 100);
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &consensus_hash,
+            &block_header_hash,
+            tx_gap.txid(),
+            tx_gap.serialize_to_vec(),
+            100,
+            1,
+            &origin,
+            10,
+            &sponsor,
+            10,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            MemPoolDB::get_next_mineable_nonce(&mempool_tx, &origin, 5).unwrap(),
+            8
+        );
+
+        // the allowed window never extends past MAXIMUM_MEMPOOL_TX_CHAINING transactions ahead
+        // of the account nonce, even if every nonce in between is actually present
+        let other_origin = StacksAddress {
+            version: 22,
+            bytes: Hash160([0xcc; 20]),
+        };
+        for i in 0..(MAXIMUM_MEMPOOL_TX_CHAINING + 5) {
+            let tx = make_rbf_test_tx(100 + i, 100);
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &consensus_hash,
+                &block_header_hash,
+                tx.txid(),
+                tx.serialize_to_vec(),
+                100,
+                1,
+                &other_origin,
+                i,
+                &sponsor,
+                200 + i,
+                None,
+            )
+            .unwrap();
+        }
+        assert_eq!(
+            MemPoolDB::get_next_mineable_nonce(&mempool_tx, &other_origin, 0).unwrap(),
+            MAXIMUM_MEMPOOL_TX_CHAINING
+        );
+    }
+
+    #[test]
+    fn mempool_db_test_blocks_confirmed() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "mempool_db_test_blocks_confirmed");
+        let chainstate_path = chainstate_path("mempool_db_test_blocks_confirmed");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let consensus_hash = ConsensusHash([0x1; 20]);
+        let block_header_hash = BlockHeaderHash([0x2; 32]);
+
+        let alice = StacksAddress {
+            version: 22,
+            bytes: Hash160([0x1; 20]),
+        };
+        let bob = StacksAddress {
+            version: 22,
+            bytes: Hash160([0x2; 20]),
+        };
+        let carol = StacksAddress {
+            version: 22,
+            bytes: Hash160([0x3; 20]),
+        };
+
+        {
+            let mut mempool_tx = mempool.tx_begin().unwrap();
+
+            // alice: a contiguous run of origin nonces 0, 1, 2
+            for nonce in 0..3u64 {
+                let tx = make_rbf_test_tx(nonce, 100);
+                MemPoolDB::try_add_tx(
+                    &mut mempool_tx,
+                    &mut chainstate,
+                    &consensus_hash,
+                    &block_header_hash,
+                    tx.txid(),
+                    tx.serialize_to_vec(),
+                    100,
+                    1,
+                    &alice,
+                    nonce,
+                    &alice,
+                    nonce,
+                    None,
+                )
+                .unwrap();
+            }
+
+            // bob: origin nonces 0, 1, 3, 4 -- a gap left at nonce 2
+            for nonce in [0u64, 1, 3, 4].iter() {
+                let tx = make_rbf_test_tx(10 + *nonce, 100);
+                MemPoolDB::try_add_tx(
+                    &mut mempool_tx,
+                    &mut chainstate,
+                    &consensus_hash,
+                    &block_header_hash,
+                    tx.txid(),
+                    tx.serialize_to_vec(),
+                    100,
+                    1,
+                    &bob,
+                    *nonce,
+                    &bob,
+                    *nonce,
+                    None,
+                )
+                .unwrap();
+            }
+
+            // carol: acts only as a sponsor, at nonce 0
+            let tx_sponsor = make_rbf_test_tx(20, 100);
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &consensus_hash,
+                &block_header_hash,
+                tx_sponsor.txid(),
+                tx_sponsor.serialize_to_vec(),
+                100,
+                1,
+                &alice,
+                100,
+                &carol,
+                0,
+                None,
+            )
+            .unwrap();
+
+            mempool_tx.commit().unwrap();
+        }
+
+        let alice_nonce = |nonce: u64| {
+            MemPoolDB::get_tx_metadata_by_address(&mempool.db, true, &alice, nonce).unwrap()
+        };
+        let bob_nonce = |nonce: u64| {
+            MemPoolDB::get_tx_metadata_by_address(&mempool.db, true, &bob, nonce).unwrap()
+        };
+        let carol_sponsor_nonce = |nonce: u64| {
+            MemPoolDB::get_tx_metadata_by_address(&mempool.db, false, &carol, nonce).unwrap()
+        };
+
+        assert!(alice_nonce(0).is_some());
+        assert!(alice_nonce(1).is_some());
+        assert!(alice_nonce(2).is_some());
+        assert!(bob_nonce(0).is_some());
+        assert!(bob_nonce(1).is_some());
+        assert!(bob_nonce(3).is_some());
+        assert!(bob_nonce(4).is_some());
+        assert!(carol_sponsor_nonce(0).is_some());
+
+        // confirm alice through nonce 1 (contiguous run) -- nonce 2 remains untouched since it's
+        // still reachable from the new account nonce
+        mempool
+            .blocks_confirmed(&[(alice.clone(), 1)], None)
+            .unwrap();
+        assert!(alice_nonce(0).is_none());
+        assert!(alice_nonce(1).is_none());
+        assert!(alice_nonce(2).is_some());
+
+        // confirm bob through nonce 1 -- this leaves a gap at nonce 2 (never in the mempool), so
+        // the now-unreachable nonces 3 and 4 must be cascaded away too
+        mempool.blocks_confirmed(&[(bob.clone(), 1)], None).unwrap();
+        assert!(bob_nonce(0).is_none());
+        assert!(bob_nonce(1).is_none());
+        assert!(bob_nonce(3).is_none());
+        assert!(bob_nonce(4).is_none());
+
+        // confirm carol through nonce 0 -- her only mempool entry is as a sponsor, which the
+        // origin/sponsor-nonce tracking must catch independently of the origin side
+        mempool
+            .blocks_confirmed(&[(carol.clone(), 0)], None)
+            .unwrap();
+        assert!(carol_sponsor_nonce(0).is_none());
+
+        // alice's nonce-2 tx is still untouched by any of the above
+        assert!(alice_nonce(2).is_some());
+    }
+
+    #[test]
+    fn mempool_db_test_reintroduced_tiebreak() {
+        let mut chainstate = instantiate_chainstate(
+            false,
+            0x80000000,
+            "mempool_db_test_reintroduced_tiebreak",
+        );
+        let chainstate_path = chainstate_path("mempool_db_test_reintroduced_tiebreak");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let consensus_hash = ConsensusHash([0x1; 20]);
+        let block_header_hash = BlockHeaderHash([0x2; 32]);
+
+        let alice = StacksAddress {
+            version: 22,
+            bytes: Hash160([0x1; 20]),
+        };
+        let bob = StacksAddress {
+            version: 22,
+            bytes: Hash160([0x2; 20]),
+        };
+
+        {
+            let mut mempool_tx = mempool.tx_begin().unwrap();
+
+            // alice and bob each submit one tx of the same fee and the same (fixed) serialized
+            // length, so they tie on fee rate
+            let tx_alice = make_rbf_test_tx(1, 100);
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &consensus_hash,
+                &block_header_hash,
+                tx_alice.txid(),
+                tx_alice.serialize_to_vec(),
+                100,
+                1,
+                &alice,
+                0,
+                &alice,
+                0,
+                None,
+            )
+            .unwrap();
+
+            let tx_bob = make_rbf_test_tx(2, 100);
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &consensus_hash,
+                &block_header_hash,
+                tx_bob.txid(),
+                tx_bob.serialize_to_vec(),
+                100,
+                1,
+                &bob,
+                0,
+                &bob,
+                0,
+                None,
+            )
+            .unwrap();
+
+            // flag bob's tx as pulled back in from a losing fork
+            MemPoolDB::mark_reintroduced(&mut mempool_tx, &tx_bob.txid()).unwrap();
+
+            mempool_tx.commit().unwrap();
+        }
+
+        let origins = mempool
+            .find_origin_addresses_by_descending_fees(0, 10, 0, 0, 10)
+            .unwrap();
+        assert_eq!(origins.len(), 2);
+        assert_eq!(
+            origins[0], bob,
+            "on a fee-rate tie, the reintroduced origin should be preferred"
+        );
+    }
+
+    #[test]
+    fn mempool_db_test_clear_before_coinbase_height() {
+        let mut chainstate = instantiate_chainstate(
+            false,
+            0x80000000,
+            "mempool_db_test_clear_before_coinbase_height",
+        );
+        let chainstate_path = chainstate_path("mempool_db_test_clear_before_coinbase_height");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let consensus_hash = ConsensusHash([0x1; 20]);
+        let block_header_hash = BlockHeaderHash([0x2; 32]);
+        let addr = StacksAddress {
+            version: 22,
+            bytes: Hash160([0x1; 20]),
+        };
+
+        let tx = make_rbf_test_tx(1, 100);
+        {
+            let mut mempool_tx = mempool.tx_begin().unwrap();
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &consensus_hash,
+                &block_header_hash,
+                tx.txid(),
+                tx.serialize_to_vec(),
+                100,
+                1,
+                &addr,
+                0,
+                &addr,
+                0,
+                None,
+            )
+            .unwrap();
+            mempool_tx.commit().unwrap();
+        }
+
+        // the test chainstate doesn't track a real coinbase height, so `try_add_tx` falls back
+        // to the per-fork height (1) -- `clear_before_coinbase_height` at that same height is a
+        // no-op, and at a higher one collects the tx
+        mempool.clear_before_coinbase_height(1).unwrap();
+        assert!(mempool.has_tx(&tx.txid()));
+
+        mempool.clear_before_coinbase_height(2).unwrap();
+        assert!(!mempool.has_tx(&tx.txid()));
+    }
+
+    #[test]
+    fn test_sync_data_spans_whole_tenure_at_one_coinbase_height() {
+        let mut chainstate = instantiate_chainstate(
+            false,
+            0x80000000,
+            "sync_data_spans_whole_tenure_at_one_coinbase_height",
+        );
+        let chainstate_path =
+            chainstate_path("sync_data_spans_whole_tenure_at_one_coinbase_height");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let addr = StacksAddress {
+            version: 22,
+            bytes: Hash160([0x1; 20]),
+        };
+
+        // under Nakamoto, many Stacks blocks can share one tenure (and therefore one coinbase
+        // height).  Admit a tx at each of three distinct per-fork heights within that tenure --
+        // this test's chainstate always falls back to the raw height (see
+        // `mempool_db_test_clear_before_coinbase_height`), so the shared coinbase height a real
+        // chain tip would have reported for all three blocks is patched in directly afterward.
+        let txs = [
+            make_rbf_test_tx(1, 100),
+            make_rbf_test_tx(2, 100),
+            make_rbf_test_tx(3, 100),
+        ];
+        for (i, (tx, raw_height)) in txs.iter().zip([10u64, 11, 12].iter()).enumerate() {
+            let mut mempool_tx = mempool.tx_begin().unwrap();
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &ConsensusHash([(0x1 + i) as u8; 20]),
+                &BlockHeaderHash([(0x2 + i) as u8; 32]),
+                tx.txid(),
+                tx.serialize_to_vec(),
+                100,
+                *raw_height,
+                &addr,
+                i as u64,
+                &addr,
+                i as u64,
+                None,
+            )
+            .unwrap();
+            mempool_tx.commit().unwrap();
+
+            mempool
+                .conn()
+                .execute(
+                    "UPDATE mempool SET coinbase_height = ?1 WHERE txid = ?2",
+                    &[&50i64 as &dyn ToSql, &tx.txid()],
+                )
+                .unwrap();
+        }
+
+        // the sync set should span the whole tenure, regardless of how many blocks (and
+        // therefore how many distinct raw heights) it took to build
+        let sync_data = mempool.make_mempool_sync_data().unwrap();
+        let inventory = match sync_data {
+            MemPoolSyncData::Inventory(ref txids) => txids.clone(),
+            other => panic!("expected an Inventory sync mode, got {:?}", other),
+        };
+        let inventory_set: HashSet<Txid> = inventory.into_iter().collect();
+        assert_eq!(inventory_set.len(), txs.len());
+        for tx in txs.iter() {
+            assert!(inventory_set.contains(&tx.txid()));
+        }
+
+        // a peer with an empty inventory is missing all of them, at the shared coinbase height
+        let missing: HashSet<Txid> = mempool
+            .find_missing_txids(&MemPoolSyncData::Inventory(vec![]), 50)
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(missing.len(), txs.len());
+        for tx in txs.iter() {
+            assert!(missing.contains(&tx.txid()));
+        }
+    }
+
+    #[test]
+    fn test_bloom_counter_prunes_by_coinbase_height_across_reorg() {
+        let mut chainstate = instantiate_chainstate(
+            false,
+            0x80000000,
+            "bloom_counter_prunes_by_coinbase_height_across_reorg",
+        );
+        let chainstate_path =
+            chainstate_path("bloom_counter_prunes_by_coinbase_height_across_reorg");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let addr = StacksAddress {
+            version: 22,
+            bytes: Hash160([0x1; 20]),
+        };
+
+        // tx_a is admitted at a tall raw height, well before a sortition-level reorg rewinds the
+        // raw chain height back down; tx_b is admitted just after the reorg, at a raw height
+        // lower than tx_a's.  This test's chainstate always falls back to the raw height (see
+        // `mempool_db_test_clear_before_coinbase_height`), so the monotonic coinbase heights a
+        // real, reorg-surviving chain tip would have reported are patched in directly afterward.
+        let tx_a = make_rbf_test_tx(1, 100);
+        let tx_b = make_rbf_test_tx(2, 100);
+        let tx_c = make_rbf_test_tx(3, 100);
+
+        for (i, (tx, raw_height)) in [(&tx_a, 100u64), (&tx_b, 5u64)].iter().enumerate() {
+            let mut mempool_tx = mempool.tx_begin().unwrap();
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &ConsensusHash([(0x1 + i) as u8; 20]),
+                &BlockHeaderHash([(0x2 + i) as u8; 32]),
+                tx.txid(),
+                tx.serialize_to_vec(),
+                100,
+                *raw_height,
+                &addr,
+                i as u64,
+                &addr,
+                i as u64,
+                None,
+            )
+            .unwrap();
+            mempool_tx.commit().unwrap();
+        }
+
+        // patch in the true, monotonic coinbase heights: tx_a's tenure was at coinbase height
+        // 10, and despite the raw height rollback, tx_b's tenure had already advanced to 11.
+        mempool
+            .conn()
+            .execute(
+                "UPDATE mempool SET coinbase_height = ?1 WHERE txid = ?2",
+                &[&10i64 as &dyn ToSql, &tx_a.txid()],
+            )
+            .unwrap();
+        mempool
+            .conn()
+            .execute(
+                "UPDATE mempool SET coinbase_height = ?1 WHERE txid = ?2",
+                &[&11i64 as &dyn ToSql, &tx_b.txid()],
+            )
+            .unwrap();
+
+        // admit tx_c at the tenure's coinbase height of 12 (BLOOM_COUNTER_DEPTH is 2, so this
+        // advances the bloom window past tx_a's coinbase height of 10, but not past tx_b's 11)
+        {
+            let mut mempool_tx = mempool.tx_begin().unwrap();
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &ConsensusHash([0x3; 20]),
+                &BlockHeaderHash([0x4; 32]),
+                tx_c.txid(),
+                tx_c.serialize_to_vec(),
+                100,
+                12,
+                &addr,
+                2,
+                &addr,
+                2,
+                None,
+            )
+            .unwrap();
+            mempool_tx.commit().unwrap();
+        }
+
+        let bf = mempool.get_txid_bloom_filter().unwrap();
+        assert!(
+            !bf.contains_raw(&tx_a.txid().0),
+            "tx_a's coinbase height (10) is outside the bloom window once the tip reaches \
+             coinbase height 12, even though its raw admission height (100) was never exceeded"
+        );
+        assert!(
+            bf.contains_raw(&tx_b.txid().0),
+            "tx_b's coinbase height (11) is still inside the bloom window, even though its raw \
+             admission height (5) is lower than tx_a's"
+        );
+        assert!(bf.contains_raw(&tx_c.txid().0));
+
+        // pruning the bloom filter never removes the underlying mempool rows
+        assert!(mempool.has_tx(&tx_a.txid()));
+        assert!(mempool.has_tx(&tx_b.txid()));
+        assert!(mempool.has_tx(&tx_c.txid()));
+    }
+
+    #[test]
+    fn mempool_db_test_garbage_collect_expired() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "mempool_db_test_garbage_collect_expired");
+        let chainstate_path = chainstate_path("mempool_db_test_garbage_collect_expired");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let consensus_hash = ConsensusHash([0x1; 20]);
+        let block_header_hash = BlockHeaderHash([0x2; 32]);
+        let addr = StacksAddress {
+            version: 22,
+            bytes: Hash160([0x1; 20]),
+        };
+
+        let tx_fresh = make_rbf_test_tx(1, 100);
+        let tx_old = make_rbf_test_tx(2, 100);
+        {
+            let mut mempool_tx = mempool.tx_begin().unwrap();
+            for (i, tx) in [&tx_fresh, &tx_old].iter().enumerate() {
+                MemPoolDB::try_add_tx(
+                    &mut mempool_tx,
+                    &mut chainstate,
+                    &consensus_hash,
+                    &block_header_hash,
+                    tx.txid(),
+                    tx.serialize_to_vec(),
+                    100,
+                    1,
+                    &addr,
+                    i as u64,
+                    &addr,
+                    i as u64,
+                    None,
+                )
+                .unwrap();
+            }
+            // backdate `tx_old` well past any TTL we'll test with, as though it had been sitting
+            // in the mempool for a very long time
+            mempool_tx
+                .execute(
+                    "UPDATE mempool SET accept_time = 0 WHERE txid = ?1",
+                    &[&tx_old.txid()],
+                )
+                .unwrap();
+            mempool_tx.commit().unwrap();
+        }
+
+        mempool.clear_expired(DEFAULT_MEMPOOL_TX_TTL).unwrap();
+        assert!(
+            mempool.has_tx(&tx_fresh.txid()),
+            "a freshly-accepted tx should survive a TTL sweep"
+        );
+        assert!(
+            !mempool.has_tx(&tx_old.txid()),
+            "a tx backdated past the TTL should be collected"
+        );
+    }
+
+    #[test]
+    fn mempool_db_test_garbage_collect_stale_nonces() {
+        let mut chainstate = instantiate_chainstate(
+            false,
+            0x80000000,
+            "mempool_db_test_garbage_collect_stale_nonces",
+        );
+        let chainstate_path = chainstate_path("mempool_db_test_garbage_collect_stale_nonces");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let consensus_hash = ConsensusHash([0x1; 20]);
+        let block_header_hash = BlockHeaderHash([0x2; 32]);
+        let alice = StacksAddress {
+            version: 22,
+            bytes: Hash160([0x1; 20]),
+        };
+        let bob = StacksAddress {
+            version: 22,
+            bytes: Hash160([0x2; 20]),
+        };
 
-        // check that the transaction was replaced
-        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &old_txid).unwrap());
-        assert!(MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+        // alice's tx sits within reach of her confirmed nonce; bob's was submitted against a
+        // nonce so far ahead of his confirmed nonce that it can never be chained to
+        let tx_alice = make_rbf_test_tx(1, 100);
+        let tx_bob = make_rbf_test_tx(2, 100);
+        {
+            let mut mempool_tx = mempool.tx_begin().unwrap();
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &consensus_hash,
+                &block_header_hash,
+                tx_alice.txid(),
+                tx_alice.serialize_to_vec(),
+                100,
+                1,
+                &alice,
+                2,
+                &alice,
+                2,
+                None,
+            )
+            .unwrap();
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &consensus_hash,
+                &block_header_hash,
+                tx_bob.txid(),
+                tx_bob.serialize_to_vec(),
+                100,
+                1,
+                &bob,
+                1000,
+                &bob,
+                1000,
+                None,
+            )
+            .unwrap();
+            mempool_tx.commit().unwrap();
+        }
 
-        let tx_info_after =
-            MemPoolDB::get_tx_metadata_by_address(&mempool_tx, true, &origin_address, origin_nonce)
-                .unwrap()
-                .unwrap();
-        assert!(tx_info_after != tx_info.metadata);
+        let account_nonces = &[(alice, 0), (bob, 0)];
+        mempool
+            .garbage_collect_stale_nonces(account_nonces, DEFAULT_MAX_NONCE_GAP, None)
+            .unwrap();
 
-        // test retrieval -- transaction should have been replaced because it has a higher fee
-        let tx_info_opt = MemPoolDB::get_tx(&mempool_tx, &txid).unwrap();
-        let tx_info = tx_info_opt.unwrap();
-        assert_eq!(tx_info.metadata, tx_info_after);
-        assert_eq!(tx_info.metadata.len, second_len);
-        assert_eq!(tx_info.metadata.tx_fee, 124);
+        assert!(
+            mempool.has_tx(&tx_alice.txid()),
+            "a tx within the chaining window of the confirmed nonce should survive"
+        );
+        assert!(
+            !mempool.has_tx(&tx_bob.txid()),
+            "a tx whose nonce can never be chained to should be collected"
+        );
     }
 
     #[test]
@@ -2640,6 +6640,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_txtag_keyed_siphash() {
+        let txid = Txid([0x1; 32]);
+
+        let seed_a = [0xaa; 32];
+        let seed_b = [0xbb; 32];
+
+        // two nodes sharing a seed agree on every txid's tag
+        assert_eq!(
+            TxTag::from_seed_and_txid(&seed_a, &txid),
+            TxTag::from_seed_and_txid(&seed_a, &txid)
+        );
+
+        // a different seed yields a different keyed hasher, and thus (w.h.p.) a different tag
+        assert_ne!(
+            TxTag::from_seed_and_txid(&seed_a, &txid),
+            TxTag::from_seed_and_txid(&seed_b, &txid)
+        );
+    }
+
     #[test]
     fn test_txtags() {
         let mut chainstate = instantiate_chainstate(false, 0x80000000, "mempool_txtags");
@@ -2878,6 +6898,21 @@ mod tests {
                             assert!(recent_set.contains(tag));
                         }
                     }
+                    MemPoolSyncData::Inventory(ref inv_txids) => {
+                        eprintln!(
+                            "inventory({}); txids.len() == {}",
+                            block_height,
+                            txids.len()
+                        );
+                        let recent_txids = mempool.get_bloom_txids().unwrap();
+                        let inv_set: HashSet<Txid> = inv_txids.iter().cloned().collect();
+
+                        // an inventory is exact: every recent txid appears, and nothing else does
+                        assert_eq!(inv_set.len(), recent_txids.len());
+                        for txid in recent_txids.iter() {
+                            assert!(inv_set.contains(txid));
+                        }
+                    }
                 }
 
                 let mut nonrecent_fp_rate = 0.0f64;
@@ -2918,6 +6953,88 @@ mod tests {
         assert!((avg_nonrecent_fp_rate - BLOOM_COUNTER_ERROR_RATE).abs() < 0.001);
     }
 
+    #[test]
+    fn test_make_mempool_sync_data_inventory_mode() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "make_mempool_sync_data_inventory_mode");
+        let chainstate_path = chainstate_path("make_mempool_sync_data_inventory_mode");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let addr = StacksAddress {
+            version: 1,
+            bytes: Hash160([0xff; 20]),
+        };
+
+        // a small mempool, well under DEFAULT_MAX_INVENTORY_TXS, should sync via an exact
+        // inventory rather than a tag set or a bloom filter
+        let mut txids = vec![];
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        for i in 0..5u64 {
+            let pk = StacksPrivateKey::new();
+            let mut tx = StacksTransaction {
+                version: TransactionVersion::Testnet,
+                chain_id: 0x80000000,
+                auth: TransactionAuth::from_p2pkh(&pk).unwrap(),
+                anchor_mode: TransactionAnchorMode::Any,
+                post_condition_mode: TransactionPostConditionMode::Allow,
+                post_conditions: vec![],
+                payload: TransactionPayload::TokenTransfer(
+                    addr.to_account_principal(),
+                    123,
+                    TokenTransferMemo([0u8; 34]),
+                ),
+            };
+            tx.set_tx_fee(1000);
+            tx.set_origin_nonce(i);
+
+            let txid = tx.txid();
+            let tx_bytes = tx.serialize_to_vec();
+            let origin_addr = tx.origin_address();
+            let origin_nonce = tx.get_origin_nonce();
+            let sponsor_addr = tx.sponsor_address().unwrap_or(origin_addr.clone());
+            let sponsor_nonce = tx.get_sponsor_nonce().unwrap_or(origin_nonce);
+            let tx_fee = tx.get_tx_fee();
+
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &ConsensusHash([0x1; 20]),
+                &BlockHeaderHash([0x2; 32]),
+                txid.clone(),
+                tx_bytes,
+                tx_fee,
+                10,
+                &origin_addr,
+                origin_nonce,
+                &sponsor_addr,
+                sponsor_nonce,
+                None,
+            )
+            .unwrap();
+            txids.push(txid);
+        }
+        mempool_tx.commit().unwrap();
+
+        let sync_data = mempool.make_mempool_sync_data().unwrap();
+        let inventory = match sync_data {
+            MemPoolSyncData::Inventory(ref txids) => txids.clone(),
+            _ => panic!("expected an Inventory sync mode for a small mempool"),
+        };
+        let inventory_set: HashSet<Txid> = inventory.into_iter().collect();
+        assert_eq!(inventory_set.len(), txids.len());
+        for txid in txids.iter() {
+            assert!(inventory_set.contains(txid));
+        }
+
+        // a peer reconciling against this inventory gets exactly the complement, with no false
+        // positives: tell it we already have every tx except the last one
+        let peer_has: Vec<Txid> = txids[..txids.len() - 1].to_vec();
+        let missing = mempool
+            .find_missing_txids(&MemPoolSyncData::Inventory(peer_has), 10)
+            .unwrap();
+        assert_eq!(missing, vec![txids[txids.len() - 1].clone()]);
+    }
+
     #[test]
     fn test_find_next_missing_transactions() {
         let mut chainstate =
@@ -3141,6 +7258,261 @@ mod tests {
         assert_eq!(old_txs.len(), 0);
     }
 
+    #[test]
+    fn test_find_next_missing_transactions_by_fee_rate() {
+        let mut chainstate = instantiate_chainstate(
+            false,
+            0x80000000,
+            "find_next_missing_transactions_by_fee_rate",
+        );
+        let chainstate_path = chainstate_path("find_next_missing_transactions_by_fee_rate");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let addr = StacksAddress {
+            version: 1,
+            bytes: Hash160([0xff; 20]),
+        };
+
+        // three txs of the same (fixed) serialized length but distinct fees, so they have
+        // distinct, strictly-ordered fee rates
+        let fees = [300, 200, 100];
+        let mut txids_by_fee = vec![];
+
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        for (i, fee) in fees.iter().enumerate() {
+            let tx = make_rbf_test_tx(i as u64, *fee);
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &ConsensusHash([0x1; 20]),
+                &BlockHeaderHash([0x2; 32]),
+                tx.txid(),
+                tx.serialize_to_vec(),
+                *fee,
+                1,
+                &addr,
+                i as u64,
+                &addr,
+                i as u64,
+                None,
+            )
+            .unwrap();
+            txids_by_fee.push(tx.txid());
+        }
+        mempool_tx.commit().unwrap();
+
+        // with an empty tag set (peer has nothing), the highest fee rate comes back first
+        let txs = mempool
+            .find_next_missing_transactions_by_fee_rate(
+                &MemPoolSyncData::TxTags([0u8; 32], vec![]),
+                0,
+                None,
+                1,
+                10,
+            )
+            .unwrap();
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].txid(), txids_by_fee[0], "highest-fee tx should come back first");
+
+        // paginating with the returned tx's (fee_rate, txid) as the cursor picks up with the
+        // next-highest fee rate, not the one already returned
+        let returned_tx_info = MemPoolDB::get_tx(mempool.conn(), &txs[0].txid())
+            .unwrap()
+            .unwrap();
+        let cursor = Some((returned_tx_info.metadata.fee_rate(), txs[0].txid()));
+        let txs = mempool
+            .find_next_missing_transactions_by_fee_rate(
+                &MemPoolSyncData::TxTags([0u8; 32], vec![]),
+                0,
+                cursor,
+                1,
+                10,
+            )
+            .unwrap();
+        assert_eq!(txs.len(), 1);
+        assert_eq!(
+            txs[0].txid(),
+            txids_by_fee[1],
+            "cursor should skip the already-returned highest-fee tx"
+        );
+
+        // a full txtag set means every tx is already represented at the peer, so none come back
+        let seed = [0u8; 32];
+        let txtags = mempool.get_txtags(&seed).unwrap();
+        let txs = mempool
+            .find_next_missing_transactions_by_fee_rate(
+                &MemPoolSyncData::TxTags(seed, txtags),
+                0,
+                None,
+                (fees.len()) as u64,
+                10,
+            )
+            .unwrap();
+        assert_eq!(txs.len(), 0);
+    }
+
+    #[test]
+    fn test_mempool_reconciliation_converges() {
+        // two independently-filled mempools, each missing some of what the other has, converge
+        // to the same txid set after one round of: exchange sync data, compute what's missing
+        // via the peer's sync data, fetch exactly those txs, and add them locally.
+        let mut alice_chainstate =
+            instantiate_chainstate(false, 0x80000000, "mempool_reconciliation_converges_alice");
+        let alice_chainstate_path =
+            chainstate_path("mempool_reconciliation_converges_alice");
+        let mut alice_mempool =
+            MemPoolDB::open(false, 0x80000000, &alice_chainstate_path).unwrap();
+
+        let mut bob_chainstate =
+            instantiate_chainstate(false, 0x80000000, "mempool_reconciliation_converges_bob");
+        let bob_chainstate_path = chainstate_path("mempool_reconciliation_converges_bob");
+        let mut bob_mempool = MemPoolDB::open(false, 0x80000000, &bob_chainstate_path).unwrap();
+
+        let addr = StacksAddress {
+            version: 1,
+            bytes: Hash160([0xff; 20]),
+        };
+
+        // alice has txs at nonces 0..5, bob has txs at nonces 3..8 -- nonces 3 and 4 overlap
+        let mut alice_txs = vec![];
+        let mut alice_tx = alice_mempool.tx_begin().unwrap();
+        for nonce in 0..5u64 {
+            let tx = make_rbf_test_tx(nonce, 100 + nonce);
+            MemPoolDB::try_add_tx(
+                &mut alice_tx,
+                &mut alice_chainstate,
+                &ConsensusHash([0x1; 20]),
+                &BlockHeaderHash([0x2; 32]),
+                tx.txid(),
+                tx.serialize_to_vec(),
+                100 + nonce,
+                1,
+                &addr,
+                nonce,
+                &addr,
+                nonce,
+                None,
+            )
+            .unwrap();
+            alice_txs.push(tx);
+        }
+        alice_tx.commit().unwrap();
+
+        let mut bob_txs = vec![];
+        let mut bob_tx = bob_mempool.tx_begin().unwrap();
+        for nonce in 3..8u64 {
+            // the overlapping nonces are the very same txs alice has, not fresh ones
+            let tx = if nonce < 5 {
+                alice_txs[nonce as usize].clone()
+            } else {
+                make_rbf_test_tx(nonce, 100 + nonce)
+            };
+            MemPoolDB::try_add_tx(
+                &mut bob_tx,
+                &mut bob_chainstate,
+                &ConsensusHash([0x1; 20]),
+                &BlockHeaderHash([0x2; 32]),
+                tx.txid(),
+                tx.serialize_to_vec(),
+                100 + nonce,
+                1,
+                &addr,
+                nonce,
+                &addr,
+                nonce,
+                None,
+            )
+            .unwrap();
+            bob_txs.push(tx);
+        }
+        bob_tx.commit().unwrap();
+
+        let union_txids: HashSet<_> = alice_txs
+            .iter()
+            .chain(bob_txs.iter())
+            .map(|tx| tx.txid())
+            .collect();
+        assert_eq!(union_txids.len(), 8, "nonces 3 and 4 should overlap");
+
+        // one reconciliation round: each side learns the other's sync data, computes what it's
+        // missing, and fetches exactly those txs
+        let alice_sync_data = alice_mempool.make_mempool_sync_data().unwrap();
+        let bob_sync_data = bob_mempool.make_mempool_sync_data().unwrap();
+
+        let bob_missing_from_alice = alice_mempool
+            .find_missing_txids(&bob_sync_data, 0)
+            .unwrap();
+        let alice_missing_from_bob = bob_mempool
+            .find_missing_txids(&alice_sync_data, 0)
+            .unwrap();
+
+        assert_eq!(bob_missing_from_alice.len(), 3, "bob is missing alice's nonces 0, 1, 2");
+        assert_eq!(alice_missing_from_bob.len(), 3, "alice is missing bob's nonces 5, 6, 7");
+
+        // simulate delivering exactly the missing txs each way
+        let mut bob_tx = bob_mempool.tx_begin().unwrap();
+        for txid in bob_missing_from_alice.iter() {
+            let tx_info = MemPoolDB::get_tx(alice_mempool.conn(), txid)
+                .unwrap()
+                .unwrap();
+            MemPoolDB::try_add_tx(
+                &mut bob_tx,
+                &mut bob_chainstate,
+                &tx_info.metadata.consensus_hash,
+                &tx_info.metadata.block_header_hash,
+                tx_info.tx.txid(),
+                tx_info.tx.serialize_to_vec(),
+                tx_info.metadata.tx_fee,
+                1,
+                &addr,
+                tx_info.metadata.origin_nonce,
+                &addr,
+                tx_info.metadata.sponsor_nonce,
+                None,
+            )
+            .unwrap();
+        }
+        bob_tx.commit().unwrap();
+
+        let mut alice_tx = alice_mempool.tx_begin().unwrap();
+        for txid in alice_missing_from_bob.iter() {
+            let tx_info = MemPoolDB::get_tx(bob_mempool.conn(), txid)
+                .unwrap()
+                .unwrap();
+            MemPoolDB::try_add_tx(
+                &mut alice_tx,
+                &mut alice_chainstate,
+                &tx_info.metadata.consensus_hash,
+                &tx_info.metadata.block_header_hash,
+                tx_info.tx.txid(),
+                tx_info.tx.serialize_to_vec(),
+                tx_info.metadata.tx_fee,
+                1,
+                &addr,
+                tx_info.metadata.origin_nonce,
+                &addr,
+                tx_info.metadata.sponsor_nonce,
+                None,
+            )
+            .unwrap();
+        }
+        alice_tx.commit().unwrap();
+
+        let alice_final: HashSet<_> = alice_txs
+            .iter()
+            .map(|tx| tx.txid())
+            .chain(alice_missing_from_bob.iter().cloned())
+            .collect();
+        let bob_final: HashSet<_> = bob_txs
+            .iter()
+            .map(|tx| tx.txid())
+            .chain(bob_missing_from_alice.iter().cloned())
+            .collect();
+
+        assert_eq!(alice_final, union_txids, "alice should now have the full union");
+        assert_eq!(bob_final, union_txids, "bob should now have the full union");
+    }
+
     #[test]
     fn test_stream_txs() {
         let mut chainstate = instantiate_chainstate(false, 0x80000000, "test_stream_txs");
@@ -3259,4 +7631,110 @@ mod tests {
             assert!(tx_set.contains(&tx.txid()));
         }
     }
+
+    #[test]
+    fn test_fee_estimator_confirmation_targets_are_monotonic() {
+        let _chainstate = instantiate_chainstate(
+            false,
+            0x80000000,
+            "fee_estimator_confirmation_targets_are_monotonic",
+        );
+        let path = format!(
+            "{}/fee_estimator_confirmation_targets_are_monotonic.sqlite",
+            chainstate_path("fee_estimator_confirmation_targets_are_monotonic")
+        );
+        let mut estimator = SQLiteFeeEstimator::open(&path, 0.5).unwrap();
+
+        // a synthetic population: high fee rates always confirm fast (delay 1), low fee rates
+        // take much longer (delay 40), feeding each one repeatedly so the decayed estimate
+        // converges well past the 85% confidence bar.
+        for _ in 0..50 {
+            estimator
+                .notify_confirmations(&[
+                    ProcessedTxConfirmation {
+                        fee_rate: 100.0,
+                        blocks_to_confirm: 1,
+                    },
+                    ProcessedTxConfirmation {
+                        fee_rate: 1.0,
+                        blocks_to_confirm: 40,
+                    },
+                ])
+                .unwrap();
+        }
+
+        // requiring fast confirmation (1 block) should demand at least as high a fee rate as
+        // requiring slow confirmation (50 blocks) -- the low-fee-rate population only clears the
+        // bar once the target is generous enough to include its typical delay
+        let tight = estimator.estimate_fee_rate(1, 0.85);
+        let loose = estimator.estimate_fee_rate(50, 0.85);
+
+        assert!(tight.is_some(), "the fast-confirming bucket should meet confidence at target=1");
+        assert!(loose.is_some(), "the slow-confirming bucket should meet confidence at target=50");
+        assert!(
+            tight.unwrap() >= loose.unwrap(),
+            "a tighter confirmation target should never demand a lower fee rate than a looser one"
+        );
+
+        // at a target too short for the slow population to ever meet, only the fast bucket
+        // clears the bar, so the estimate should be strictly higher than at a generous target
+        let very_tight = estimator.estimate_fee_rate(2, 0.85).unwrap();
+        assert!(
+            very_tight > loose.unwrap(),
+            "a target the slow population can't meet should estimate a strictly higher fee rate"
+        );
+    }
+
+    #[test]
+    fn test_notify_confirmed_txs_feeds_fee_estimator() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "notify_confirmed_txs_feeds_fee_estimator");
+        let chainstate_path = chainstate_path("notify_confirmed_txs_feeds_fee_estimator");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let fee_estimator_path = format!("{}/fee_estimator.sqlite", &chainstate_path);
+        let mut fee_estimator = SQLiteFeeEstimator::open(&fee_estimator_path, 0.5).unwrap();
+
+        let addr = StacksAddress {
+            version: 1,
+            bytes: Hash160([0xff; 20]),
+        };
+
+        let admission_height = 10;
+        let tx = make_rbf_test_tx(0, 100);
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &ConsensusHash([0x1; 20]),
+            &BlockHeaderHash([0x2; 32]),
+            tx.txid(),
+            tx.serialize_to_vec(),
+            100,
+            admission_height,
+            &addr,
+            0,
+            &addr,
+            0,
+            None,
+        )
+        .unwrap();
+        mempool_tx.commit().unwrap();
+
+        // this tx was admitted at `admission_height` (which `try_add_tx` records as
+        // `block_height`) and is now confirmed 3 blocks later
+        let confirmed_height = admission_height + 3;
+        mempool
+            .notify_confirmed_txs(&[tx.txid()], confirmed_height, &mut fee_estimator)
+            .unwrap();
+
+        // a target of 3 or more blocks should now be satisfiable at this tx's fee rate
+        let estimate = fee_estimator.estimate_fee_rate(3, 0.5).unwrap();
+        assert!(estimate > 0.0);
+
+        // a txid no longer present in the mempool is silently skipped, not an error
+        mempool
+            .notify_confirmed_txs(&[Txid([0xee; 32])], confirmed_height, &mut fee_estimator)
+            .unwrap();
+    }
 }
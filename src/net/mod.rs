@@ -36,12 +36,17 @@ pub mod server;
 use std::fmt;
 use std::hash::Hash;
 use std::hash::Hasher;
+use std::collections::hash_map::DefaultHasher;
+use std::sync::Once;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::error;
 use std::net::SocketAddr;
 use std::net::IpAddr;
 use std::net::Ipv4Addr;
 use std::net::Ipv6Addr;
 use std::collections::HashMap;
+use std::collections::VecDeque;
 use std::io::prelude::*;
 use std::io;
 use std::io::{Read, Write};
@@ -62,6 +67,18 @@ use serde::{Serialize, Deserialize};
 
 use regex::Regex;
 
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+
+use siphasher::sip::SipHasher24;
+
+use x25519_dalek::{StaticSecret as X25519StaticSecret, PublicKey as X25519PublicKey};
+use chacha20poly1305::ChaCha20Poly1305;
+use chacha20poly1305::aead::{Aead, NewAead, Payload};
+use sha2::{Sha256, Digest};
+use hkdf::Hkdf;
+
 use burnchains::BurnchainHeaderHash;
 use burnchains::Txid;
 
@@ -77,10 +94,14 @@ use chainstate::stacks::StacksPublicKey;
 
 use chainstate::stacks::Error as chainstate_error;
 
+use core::mempool::TxTag;
+
 use util::hash::Hash160;
 use util::hash::DOUBLE_SHA256_ENCODED_SIZE;
 use util::hash::HASH160_ENCODED_SIZE;
 
+use util::bloom::{BloomFilter, BloomNodeHasher};
+
 use util::db::Error as db_error;
 use util::db::DBConn;
 
@@ -187,6 +208,14 @@ pub enum Error {
     ClarityError(clarity_error),
     /// Catch-all for chainstate errors that don't map cleanly into network errors
     ChainstateError(String),
+    /// Peer was refused on account of a `ReasonForBan`
+    Banned(ReasonForBan),
+    /// Peer's `HandshakeData::protocol_version` is below what we require
+    UnsupportedProtocolVersion(u32),
+    /// A connection's `OutboundQueue` hit `MAX_OUTBOUND_QUEUE_DEPTH` or
+    /// `MAX_OUTBOUND_QUEUE_BYTES` before the peer could drain it.  Recoverable: the caller should
+    /// back off (e.g. stop relaying to this peer) instead of buffering more.
+    OutboundQueueFull,
 }
 
 impl fmt::Display for Error {
@@ -235,6 +264,9 @@ impl fmt::Display for Error {
             Error::ChainstateError(ref s) => fmt::Display::fmt(s, f),
             Error::ClarityError(ref e) => fmt::Display::fmt(e, f),
             Error::MARFError(ref e) => fmt::Display::fmt(e, f),
+            Error::Banned(ref reason) => write!(f, "peer is banned: {:?}", reason),
+            Error::UnsupportedProtocolVersion(ref v) => write!(f, "peer's protocol version {} is not supported", v),
+            Error::OutboundQueueFull => write!(f, "outbound message queue is full"),
         }
     }
 }
@@ -285,6 +317,9 @@ impl error::Error for Error {
             Error::ChainstateError(ref _s) => None,
             Error::ClarityError(ref e) => Some(e),
             Error::MARFError(ref e) => Some(e),
+            Error::Banned(ref _reason) => None,
+            Error::UnsupportedProtocolVersion(ref _v) => None,
+            Error::OutboundQueueFull => None,
         }
     }
 }
@@ -323,6 +358,74 @@ pub trait StacksMessageCodec {
         where Self: Sized;
     fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<Self, Error>
         where Self: Sized;
+
+    /// Like `consensus_deserialize`, but reads through a `StreamingReader` so the decoder can
+    /// enforce a running byte budget (derived from `Preamble::payload_len`, and per-type
+    /// ceilings like `BLOCKS_INV_DATA_MAX_BITLEN`) instead of requiring the whole payload to
+    /// already be buffered.  Implementations that decode a length-prefixed `Vec` (`BlocksData`,
+    /// `MicroblocksData`, `NeighborsData`, ...) must override this and call
+    /// `fd.check_budget(..)` against the declared element count *before* allocating the `Vec`,
+    /// so a bogus length descriptor fails with `Error::OverflowError` instead of an attempted
+    /// multi-gigabyte allocation.  The default just delegates to `consensus_deserialize`, which
+    /// is only safe for types with no internally-declared vector lengths of their own.
+    fn consensus_deserialize_bounded<R: Read>(fd: &mut StreamingReader<R>) -> Result<Self, Error>
+        where Self: Sized
+    {
+        Self::consensus_deserialize(fd)
+    }
+}
+
+/// Wraps a `Read` and tracks how many bytes have been pulled off it against a fixed budget, so
+/// that a single oversized or malicious length descriptor can't make us buffer an unbounded
+/// amount of data before we've even validated it.  `StacksMessageCodec::consensus_deserialize_bounded`
+/// threads one of these through decoding instead of requiring the whole payload to already be in
+/// memory.
+pub struct StreamingReader<R: Read> {
+    fd: R,
+    max_bytes: u64,
+    bytes_read: u64,
+}
+
+impl<R: Read> StreamingReader<R> {
+    pub fn new(fd: R, max_bytes: u64) -> StreamingReader<R> {
+        StreamingReader {
+            fd: fd,
+            max_bytes: max_bytes,
+            bytes_read: 0
+        }
+    }
+
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    pub fn bytes_remaining(&self) -> u64 {
+        self.max_bytes.saturating_sub(self.bytes_read)
+    }
+
+    /// Check that `additional` more bytes can be read without busting the budget, without
+    /// actually reading them -- so callers can reject an oversized length descriptor before
+    /// allocating a `Vec` to hold it.
+    pub fn check_budget(&self, additional: u64) -> Result<(), Error> {
+        if self.bytes_read.saturating_add(additional) > self.max_bytes {
+            return Err(Error::OverflowError(format!(
+                "streaming read would exceed budget of {} bytes ({} read so far, {} more requested)",
+                self.max_bytes, self.bytes_read, additional
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for StreamingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let sz = self.fd.read(buf)?;
+        self.bytes_read += sz as u64;
+        if self.bytes_read > self.max_bytes {
+            return Err(io::Error::new(io::ErrorKind::Other, format!("exceeded streaming read budget of {} bytes", self.max_bytes)));
+        }
+        Ok(sz)
+    }
 }
 
 /// A container for an IPv4 or IPv6 address.
@@ -530,13 +633,14 @@ pub struct Preamble {
     pub burn_consensus_hash: ConsensusHash,         // consensus hash at block_height
     pub burn_stable_block_height: u64,              // latest stable block height (e.g. chain tip minus 7)
     pub burn_stable_consensus_hash: ConsensusHash,  // consensus hash for burn_stable_block_height
+    pub rc_consensus_hash: ConsensusHash,           // consensus hash of the first sortition in the sender's current reward cycle -- lets a peer detect a reward-cycle fork (see Error::StaleNeighbor, which only catches height lag)
     pub additional_data: u32,                       // RESERVED; pointer to additional data (should be all 0's if not used)
     pub signature: MessageSignature,                // signature from the peer that sent this
     pub payload_len: u32                            // length of the following payload, including relayers vector
 }
 
 /// P2P preamble length (addands correspond to fields above)
-pub const PREAMBLE_ENCODED_SIZE: u32 = 
+pub const PREAMBLE_ENCODED_SIZE: u32 =
     4 +
     4 +
     4 +
@@ -544,6 +648,7 @@ pub const PREAMBLE_ENCODED_SIZE: u32 =
     CONSENSUS_HASH_ENCODED_SIZE +
     8 +
     CONSENSUS_HASH_ENCODED_SIZE +
+    CONSENSUS_HASH_ENCODED_SIZE +
     4 +
     MESSAGE_SIGNATURE_ENCODED_SIZE +
     4;
@@ -564,6 +669,239 @@ pub struct BlocksInvData {
     pub microblocks_bitvec: Vec<u8>,            // bitmap of which confirmed micrblocks the peer has, in sortition order.  microblocks_bitvec[i] & (1 << j) != 0 means that this peer has the microblocks produced by sortition 8*i + j
 }
 
+/// Request for compact block filters over a burn chain block range, so a light client can test
+/// its watched txids/addresses for membership without downloading a full `BlocksInvData` or the
+/// blocks themselves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GetCompactFilters {
+    pub consensus_hash: ConsensusHash,               // _last_ consensus hash.  Look backwards in time from this consensus hash, same as GetBlocksInv
+    pub num_blocks: u16                              // number of _prior_ blocks to ask for (not to exceed BLOCKS_INV_DATA_MAX_BITLEN)
+}
+
+/// Reply to GetCompactFilters: one CompactFilter per requested block, oldest-to-newest in the
+/// same order as GetBlocksInv::num_blocks walks backwards from consensus_hash.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompactFiltersData {
+    pub filters: Vec<CompactFilter>
+}
+
+/// Golomb-Rice parameter used to build and query `CompactFilter`s.  A random non-member element
+/// matches with probability ~`1 / 2^GOLOMB_RICE_P`; there are never false negatives.
+pub const GOLOMB_RICE_P: u32 = 19;
+
+/// A Golomb-coded set (GCS) over the txid/address elements that appeared in one Stacks block.
+/// Built by hashing each element into the range `0..N * 2^GOLOMB_RICE_P` with a block-keyed SipHash,
+/// sorting the results, delta-encoding the gaps with Golomb-Rice codes, and packing the whole
+/// thing into a byte string -- this is a fraction of the size of the equivalent BlocksInvData
+/// bit, at the cost of a tunable false-positive rate instead of exact membership.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompactFilter {
+    pub n: u32,              // number of elements encoded
+    pub encoded: Vec<u8>     // N as a varint, followed by the Golomb-Rice-coded sorted deltas
+}
+
+fn filter_key(block_header_hash: &BlockHeaderHash) -> [u8; 16] {
+    let mut key = [0u8; 16];
+    key.copy_from_slice(&block_header_hash.as_bytes()[0..16]);
+    key
+}
+
+fn filter_hash_to_range(key: &[u8; 16], element: &[u8], range: u64) -> u64 {
+    let k0 = u64::from_le_bytes([key[0], key[1], key[2], key[3], key[4], key[5], key[6], key[7]]);
+    let k1 = u64::from_le_bytes([key[8], key[9], key[10], key[11], key[12], key[13], key[14], key[15]]);
+    let mut hasher = SipHasher24::new_with_keys(k0, k1);
+    hasher.write(element);
+    let digest = hasher.finish();
+    // "fast range" reduction into 0..range, per the usual GCS construction
+    (((digest as u128) * (range as u128)) >> 64) as u64
+}
+
+fn write_filter_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let mut byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value != 0 {
+            byte |= 0x80;
+        }
+        bytes.push(byte);
+        if value == 0 {
+            break;
+        }
+    }
+}
+
+fn read_filter_varint(bytes: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let byte = *bytes.get(*pos)?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    Some(value)
+}
+
+/// Minimal MSB-first bit-packer used to build a `CompactFilter`'s Golomb-Rice code.
+struct FilterBitWriter {
+    bytes: Vec<u8>,
+    cur: u8,
+    nbits: u8,
+}
+
+impl FilterBitWriter {
+    fn new() -> FilterBitWriter {
+        FilterBitWriter { bytes: vec![], cur: 0, nbits: 0 }
+    }
+
+    fn write_bit(&mut self, bit: bool) {
+        self.cur = (self.cur << 1) | (bit as u8);
+        self.nbits += 1;
+        if self.nbits == 8 {
+            self.bytes.push(self.cur);
+            self.cur = 0;
+            self.nbits = 0;
+        }
+    }
+
+    fn write_unary(&mut self, q: u64) {
+        for _ in 0..q {
+            self.write_bit(true);
+        }
+        self.write_bit(false);
+    }
+
+    fn write_bits(&mut self, value: u64, nbits: u32) {
+        for i in (0..nbits).rev() {
+            self.write_bit((value >> i) & 1 == 1);
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.nbits > 0 {
+            self.cur <<= 8 - self.nbits;
+            self.bytes.push(self.cur);
+        }
+        self.bytes
+    }
+}
+
+/// Reads a Golomb-Rice code written by `FilterBitWriter`.
+struct FilterBitReader<'a> {
+    bytes: &'a [u8],
+    byte_idx: usize,
+    bit_idx: u8,
+}
+
+impl<'a> FilterBitReader<'a> {
+    fn new(bytes: &'a [u8]) -> FilterBitReader<'a> {
+        FilterBitReader { bytes: bytes, byte_idx: 0, bit_idx: 0 }
+    }
+
+    fn read_bit(&mut self) -> Option<bool> {
+        let byte = *self.bytes.get(self.byte_idx)?;
+        let bit = (byte >> (7 - self.bit_idx)) & 1 == 1;
+        self.bit_idx += 1;
+        if self.bit_idx == 8 {
+            self.bit_idx = 0;
+            self.byte_idx += 1;
+        }
+        Some(bit)
+    }
+
+    fn read_unary(&mut self) -> Option<u64> {
+        let mut q = 0;
+        loop {
+            if self.read_bit()? {
+                q += 1;
+            } else {
+                return Some(q);
+            }
+        }
+    }
+
+    fn read_bits(&mut self, nbits: u32) -> Option<u64> {
+        let mut value = 0;
+        for _ in 0..nbits {
+            value = (value << 1) | (self.read_bit()? as u64);
+        }
+        Some(value)
+    }
+}
+
+impl CompactFilter {
+    /// Build a compact filter over `elements` (the raw txid/address bytes of interest in the
+    /// block with header hash `block_header_hash`), using `GOLOMB_RICE_P`.
+    pub fn build(block_header_hash: &BlockHeaderHash, elements: &[Vec<u8>]) -> CompactFilter {
+        let key = filter_key(block_header_hash);
+        let n = elements.len() as u64;
+        let range = n.saturating_mul(1u64 << GOLOMB_RICE_P).max(1);
+
+        let mut values: Vec<u64> = elements.iter()
+            .map(|e| filter_hash_to_range(&key, e, range))
+            .collect();
+        values.sort_unstable();
+
+        let mut writer = FilterBitWriter::new();
+        let mut prev = 0;
+        for value in values.iter() {
+            let delta = value.saturating_sub(prev);
+            writer.write_unary(delta >> GOLOMB_RICE_P);
+            writer.write_bits(delta & ((1 << GOLOMB_RICE_P) - 1), GOLOMB_RICE_P);
+            prev = *value;
+        }
+
+        let mut encoded = vec![];
+        write_filter_varint(&mut encoded, n);
+        encoded.extend_from_slice(&writer.finish());
+
+        CompactFilter { n: n as u32, encoded: encoded }
+    }
+
+    /// Test whether `element` plausibly appeared in the block this filter was built over.  May
+    /// return a false positive with probability ~`1 / 2^GOLOMB_RICE_P`; never a false negative.
+    pub fn contains(&self, block_header_hash: &BlockHeaderHash, element: &[u8]) -> bool {
+        if self.n == 0 {
+            return false;
+        }
+
+        let key = filter_key(block_header_hash);
+        let range = (self.n as u64).saturating_mul(1u64 << GOLOMB_RICE_P).max(1);
+        let target = filter_hash_to_range(&key, element, range);
+
+        let mut pos = 0;
+        let declared_n = match read_filter_varint(&self.encoded, &mut pos) {
+            Some(declared_n) => declared_n,
+            None => return false
+        };
+
+        let mut reader = FilterBitReader::new(&self.encoded[pos..]);
+        let mut prev = 0;
+        for _ in 0..declared_n {
+            let q = match reader.read_unary() {
+                Some(q) => q,
+                None => return false
+            };
+            let r = match reader.read_bits(GOLOMB_RICE_P) {
+                Some(r) => r,
+                None => return false
+            };
+            let value = prev + ((q << GOLOMB_RICE_P) | r);
+            if value == target {
+                return true;
+            }
+            if value > target {
+                return false;
+            }
+            prev = value;
+        }
+        false
+    }
+}
+
 /// List of blocks pushed
 #[derive(Debug, Clone, PartialEq)]
 pub struct BlocksData {
@@ -619,24 +957,337 @@ pub struct NeighborsData {
 pub struct HandshakeData {
     pub addrbytes: PeerAddress,
     pub port: u16,
-    pub services: u16,                          // bit field representing services this node offers
+    pub services: u64,                          // bit field representing services this node offers; see PeerServices. Widened from u16 to leave room for future flags -- unknown high bits must be preserved and ignored, not rejected, by older code.
     pub node_public_key: StacksPublicKeyBuffer,
     pub expire_block_height: u64,               // burn block height after which this node's key will be revoked,
-    pub data_url: UrlString
+    pub data_url: UrlString,
+    pub protocol_version: u32,                  // wire-protocol version this node speaks; see MIN_PEER_PROTOCOL_VERSION
+    pub capabilities: u32                       // bitset of optional feature support this node understands; see Capabilities
 }
 
 #[repr(u8)]
 pub enum ServiceFlags {
     RELAY = 0x01,
     RPC = 0x02,
+    /// This node understands the zlib payload framing in `frame_payload`/`unframe_payload`, and
+    /// will accept a compressed payload from a peer that also advertises it.
+    COMPRESSION = 0x04,
+}
+
+/// Bitflag wrapper around `HandshakeData::services`.  Wraps a bare `u64` instead of a fixed
+/// `#[repr(u8)]` enum like `ServiceFlags` so new services can be added without breaking wire
+/// compatibility: unknown high bits must be preserved and ignored by code that doesn't
+/// recognize them yet, not rejected.  The intersection of what both sides of a handshake
+/// advertise (see `intersect`) is what the neighbor-walk and block-download logic can actually
+/// rely on the peer to serve, and gets stored on `Neighbor::services`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PeerServices(u64);
+
+impl PeerServices {
+    pub const RELAY: u64 = 0x0000_0000_0000_0001;
+    pub const RPC: u64 = 0x0000_0000_0000_0002;
+    pub const COMPRESSION: u64 = 0x0000_0000_0000_0004;
+    pub const BLOCK_ARCHIVE: u64 = 0x0000_0000_0000_0008;
+    pub const MEMPOOL_RELAY: u64 = 0x0000_0000_0000_0010;
+    pub const COMPACT_FILTERS: u64 = 0x0000_0000_0000_0020;
+    pub const MICROBLOCK_PRUNING: u64 = 0x0000_0000_0000_0040;
+
+    pub fn new(bits: u64) -> PeerServices {
+        PeerServices(bits)
+    }
+
+    pub fn bits(&self) -> u64 {
+        self.0
+    }
+
+    fn has(&self, flag: u64) -> bool {
+        self.0 & flag == flag
+    }
+
+    /// Serves the full historical block archive, rather than only recent or pruned blocks.
+    pub fn with_block_archive(&self) -> bool {
+        self.has(Self::BLOCK_ARCHIVE)
+    }
+
+    /// Participates in mempool transaction relay/sync.
+    pub fn relays_mempool(&self) -> bool {
+        self.has(Self::MEMPOOL_RELAY)
+    }
+
+    /// Can serve compact block filters to light clients (see `GetCompactFilters`).
+    pub fn serves_compact_filters(&self) -> bool {
+        self.has(Self::COMPACT_FILTERS)
+    }
+
+    /// Has pruned old confirmed microblocks, and so won't have them available to serve.
+    pub fn prunes_microblocks(&self) -> bool {
+        self.has(Self::MICROBLOCK_PRUNING)
+    }
+
+    /// The intersection of what both peers in a handshake advertised -- what either side can
+    /// actually rely on the other to serve.
+    pub fn intersect(&self, other: PeerServices) -> PeerServices {
+        PeerServices(self.0 & other.0)
+    }
+}
+
+/// Framing byte inserted between a `Preamble` and its payload once both peers advertise
+/// `ServiceFlags::COMPRESSION`, so a decoder knows whether to inflate before handing bytes to
+/// `consensus_deserialize`.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PayloadFraming {
+    Identity = 0,
+    Zlib = 1,
+}
+
+/// Only bother compressing a payload once it's at least this big; zlib's own framing overhead
+/// makes compression a net loss below this size.
+pub const COMPRESSION_MIN_PAYLOAD_LEN: usize = 512;
+
+/// Compress `payload` with zlib if doing so is actually worthwhile -- it's at least
+/// `COMPRESSION_MIN_PAYLOAD_LEN` bytes, and the compressed form (plus the framing byte) comes
+/// out smaller than the original -- and prefix the result with the `PayloadFraming` byte the
+/// receiver needs to undo it.  Falls back to an identity-framed copy otherwise, so compression
+/// stays a strict opt-in that a peer can only benefit from, never be penalized by.
+pub fn frame_payload(payload: &[u8]) -> Vec<u8> {
+    if payload.len() >= COMPRESSION_MIN_PAYLOAD_LEN {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        if encoder.write_all(payload).is_ok() {
+            if let Ok(compressed) = encoder.finish() {
+                if compressed.len() + 1 < payload.len() {
+                    let mut framed = Vec::with_capacity(compressed.len() + 1);
+                    framed.push(PayloadFraming::Zlib as u8);
+                    framed.extend_from_slice(&compressed);
+                    return framed;
+                }
+            }
+        }
+    }
+
+    let mut framed = Vec::with_capacity(payload.len() + 1);
+    framed.push(PayloadFraming::Identity as u8);
+    framed.extend_from_slice(payload);
+    framed
+}
+
+/// Undo `frame_payload`.  `max_inflated_len` must be derived from the sending peer's claimed
+/// `Preamble::payload_len` (or `HTTP_PREAMBLE_MAX_ENCODED_SIZE` for the HTTP transport) so that
+/// a compressed frame can never be used to smuggle in an inflated payload bigger than the
+/// sender claimed to send in the first place -- i.e. a decompression bomb.
+pub fn unframe_payload(framed: &[u8], max_inflated_len: usize) -> Result<Vec<u8>, Error> {
+    if framed.is_empty() {
+        return Err(Error::DeserializeError("empty payload frame".to_string()));
+    }
+
+    let (tag, body) = (framed[0], &framed[1..]);
+    if tag == PayloadFraming::Identity as u8 {
+        if body.len() > max_inflated_len {
+            return Err(Error::OverflowError(format!("identity payload of {} bytes exceeds limit of {}", body.len(), max_inflated_len)));
+        }
+        Ok(body.to_vec())
+    }
+    else if tag == PayloadFraming::Zlib as u8 {
+        let mut decoder = ZlibDecoder::new(body).take(max_inflated_len as u64 + 1);
+        let mut inflated = Vec::new();
+        decoder.read_to_end(&mut inflated).map_err(Error::ReadError)?;
+        if inflated.len() > max_inflated_len {
+            return Err(Error::OverflowError(format!("inflated payload is at least {} bytes, exceeding limit of {}", inflated.len(), max_inflated_len)));
+        }
+        Ok(inflated)
+    }
+    else {
+        Err(Error::DeserializeError(format!("unrecognized payload framing byte {}", tag)))
+    }
+}
+
+/// How harshly to respond to a peer that's committed a `ReasonForBan`-worthy protocol
+/// violation, ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PunishmentSeverity {
+    /// Drop the connection, but don't hold it against the peer.
+    Disconnect,
+    /// Drop the connection, and slow down how eagerly we reconnect to this peer.
+    Throttle,
+    /// Refuse this peer for the given number of seconds.
+    BanFor(u64),
+    /// Refuse this peer indefinitely.
+    BanPermanent,
+}
+
+/// Reasons we might disconnect from or ban a peer, each carrying its own
+/// `PunishmentSeverity`.  Centralizing violations here -- instead of returning
+/// `Error::InvalidMessage`/`Error::InvalidHandshake` and leaving each caller to decide what to
+/// do about it -- gives every kind of misbehavior a uniform, graduated response.
+///
+/// Tracking a `PeerScore` per `NeighborAddress` (decremented by `severity()` and decayed over
+/// time, with `ban_peer`/`is_banned` backed by a banned-peers table keyed by `PeerAddress`)
+/// belongs in `net::db`; that module isn't part of this snapshot, so only the violation
+/// taxonomy and its severities live here for now.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReasonForBan {
+    /// `Preamble::signature` didn't verify against the sender's claimed public key
+    BadPreambleSignature,
+    /// `BlocksInvData::bitlen` claims more bits than `block_bitvec`/`microblocks_bitvec` can hold
+    MalformedBlocksInv,
+    /// `HandshakeData::expire_block_height` was already in the past when we received it
+    ExpiredHandshake,
+    /// A message's actual payload exceeded the `payload_len` its `Preamble` declared
+    OversizedPayload,
+    /// We already processed this sender's sequence number for this message type
+    ReplayedMessage,
+    /// The peer's claimed burn header hash at one of our `ForkCheckpoint`s doesn't match ours --
+    /// it's on a fork we can never reconcile with, so there's no point walking or relaying with it
+    IncompatibleFork,
+}
+
+impl ReasonForBan {
+    /// How harshly a peer should be punished for this violation.
+    pub fn severity(&self) -> PunishmentSeverity {
+        match *self {
+            ReasonForBan::BadPreambleSignature => PunishmentSeverity::BanPermanent,
+            ReasonForBan::MalformedBlocksInv => PunishmentSeverity::BanFor(24 * 3600),
+            ReasonForBan::OversizedPayload => PunishmentSeverity::BanFor(3600),
+            ReasonForBan::ReplayedMessage => PunishmentSeverity::Disconnect,
+            ReasonForBan::ExpiredHandshake => PunishmentSeverity::Throttle,
+            ReasonForBan::IncompatibleFork => PunishmentSeverity::BanPermanent,
+        }
+    }
+}
+
+/// A hard-coded "we know this burn block's hash, no fork can change it" checkpoint.  Used to
+/// refuse handshakes with peers that claim a different burn header hash at a checkpointed
+/// height -- i.e. peers on a fork we can never reconcile with -- instead of wasting bandwidth
+/// walking or relaying with them.  Wiring a list of these into the live connection path belongs
+/// on `ConnectionOptions` (which lives in `net::connection`, not part of this snapshot); see
+/// `check_fork_checkpoints` for the comparison `net::chat`'s handshake-accept handler would call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForkCheckpoint {
+    pub burn_block_height: u64,
+    pub burn_header_hash: BurnchainHeaderHash,
+}
+
+/// Compare a peer's self-reported `(burn_block_height, burn_header_hash)` pairs -- as claimed in
+/// its handshake -- against our own `checkpoints`.  Returns `Err(Error::Banned(ReasonForBan::IncompatibleFork))`
+/// the first time a checkpointed height's hash doesn't match; `Ok(())` if every checkpoint the
+/// peer spoke to agrees with ours (a peer that simply hasn't reached a checkpoint height yet
+/// doesn't conflict with it).
+pub fn check_fork_checkpoints(checkpoints: &[ForkCheckpoint], claimed_view: &[(u64, BurnchainHeaderHash)]) -> Result<(), Error> {
+    for checkpoint in checkpoints.iter() {
+        for &(height, ref hash) in claimed_view.iter() {
+            if height == checkpoint.burn_block_height && hash != &checkpoint.burn_header_hash {
+                return Err(Error::Banned(ReasonForBan::IncompatibleFork));
+            }
+        }
+    }
+    Ok(())
 }
 
+/// The accepting peer's advertised `PeerServices` travel here via `handshake.services` rather
+/// than a separate field, so there's a single place `HandshakeData::services` is declared.
 #[derive(Debug, Clone, PartialEq)]
 pub struct HandshakeAcceptData {
     pub handshake: HandshakeData,       // this peer's handshake information
     pub heartbeat_interval: u32,        // hint as to how long this peer will remember you
 }
 
+/// Bitset of optional protocol capabilities a peer can advertise in its `HandshakeData`,
+/// independent of `ServiceFlags` (which describe what a peer offers, not what wire-format
+/// extensions it understands).  Two peers negotiate down to the intersection of what they both
+/// advertise (see `NegotiatedSession::negotiate`), so a feature -- e.g. the compression framing
+/// in `frame_payload`, a future streamed `BlocksInvData`, or paged `NeighborsData` -- can roll
+/// out gated behind a new bit here instead of requiring a hard network fork.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+    pub const COMPRESSED_PAYLOADS: Capabilities = Capabilities(0x01);
+    pub const STREAMING_INV: Capabilities = Capabilities(0x02);
+    pub const PAGED_NEIGHBORS: Capabilities = Capabilities(0x04);
+
+    pub fn empty() -> Capabilities {
+        Capabilities(0)
+    }
+
+    pub fn from_bits(bits: u32) -> Capabilities {
+        Capabilities(bits)
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    pub fn contains(&self, other: Capabilities) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn union(&self, other: Capabilities) -> Capabilities {
+        Capabilities(self.0 | other.0)
+    }
+
+    /// The set of capabilities both sides of a handshake understand -- what a negotiated
+    /// connection may actually use.
+    pub fn intersect(&self, other: Capabilities) -> Capabilities {
+        Capabilities(self.0 & other.0)
+    }
+}
+
+/// The oldest `HandshakeData::protocol_version` we'll accept from a peer.  A peer below this is
+/// rejected outright with `Error::UnsupportedProtocolVersion` instead of being allowed to
+/// silently negotiate down to a version we no longer support.
+pub const MIN_PEER_PROTOCOL_VERSION: u32 = 1;
+
+/// The outcome of negotiating protocol version and capabilities during a handshake: the lower
+/// of the two peers' `protocol_version`s, and the intersection of their `Capabilities`.  The
+/// connection layer stores this per-connection and gates optional message types on
+/// `capabilities.contains(..)`; that storage lives in `net::p2p`/`net::connection`, which this
+/// snapshot doesn't include, so only the negotiation itself lives here.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NegotiatedSession {
+    pub protocol_version: u32,
+    pub capabilities: Capabilities,
+}
+
+impl NegotiatedSession {
+    /// Negotiate a session from our own handshake and a peer's.  Returns
+    /// `Error::UnsupportedProtocolVersion` if the peer's protocol version is below
+    /// `min_protocol_version`.
+    pub fn negotiate(ours: &HandshakeData, theirs: &HandshakeData, min_protocol_version: u32) -> Result<NegotiatedSession, Error> {
+        if theirs.protocol_version < min_protocol_version {
+            return Err(Error::UnsupportedProtocolVersion(theirs.protocol_version));
+        }
+
+        Ok(NegotiatedSession {
+            protocol_version: std::cmp::min(ours.protocol_version, theirs.protocol_version),
+            capabilities: Capabilities::from_bits(ours.capabilities).intersect(Capabilities::from_bits(theirs.capabilities)),
+        })
+    }
+}
+
+/// Whether a peer's `Preamble::rc_consensus_hash` -- the consensus hash of the first sortition
+/// in its current reward cycle -- agrees with ours.  A mismatch doesn't necessarily mean the
+/// peer is on an irreconcilable fork (unlike `ForkCheckpoint`); it may simply be a reward cycle
+/// behind or ahead of us.  Rather than banning it outright, a mismatched peer should be
+/// down-ranked during the neighbor walk and deferred from full block/microblock exchange until
+/// its `rc_consensus_hash` catches up to (or converges with) ours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RewardCycleCompatibility {
+    /// We and the peer agree on the first sortition of the current reward cycle.
+    Compatible,
+    /// We and the peer disagree -- defer full sync with this peer until they agree.
+    Divergent,
+}
+
+/// Compare our `rc_consensus_hash` against a peer's.  See `RewardCycleCompatibility`.
+pub fn reward_cycle_compatibility(ours: &ConsensusHash, theirs: &ConsensusHash) -> RewardCycleCompatibility {
+    if ours == theirs {
+        RewardCycleCompatibility::Compatible
+    }
+    else {
+        RewardCycleCompatibility::Divergent
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct NackData {
     pub error_code: u32,
@@ -668,6 +1319,140 @@ pub const RELAY_DATA_ENCODED_SIZE : u32 =
     4 +
     MESSAGE_SIGNATURE_ENCODED_SIZE;
 
+/// A compact summary of one node's recent mempool contents, sent by a node requesting a mempool
+/// sync so the peer it asks can compute which of its own transactions the requester doesn't
+/// already have.  A node with very few recent transactions can just send the exact txid list
+/// (`Inventory`), which costs more bytes per entry than a tag or a bloom filter but has no false
+/// positives at all; once there are too many recent txids for that to be cheaper, a sparser
+/// mempool summarizes itself as a list of salted, truncated txid tags instead (see
+/// `TxTag::from_seed_and_txid`); and once the mempool grows large enough that the tag list would
+/// outgrow a bloom filter of the same false-positive rate, a bloom filter is sent instead (see
+/// `MemPoolDB::make_mempool_sync_data`).  `TxTags` and `BloomFilter` report membership with no
+/// false negatives, only false positives, so a peer reconciling against either will never wrongly
+/// omit a transaction the requester is missing; `Inventory` has neither.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MemPoolSyncData {
+    BloomFilter(BloomFilter<BloomNodeHasher>),
+    TxTags([u8; 32], Vec<TxTag>),
+    Inventory(Vec<Txid>),
+}
+
+/// Published to a set of well-known relay peers by a node that can't accept inbound
+/// connections, so other NATed nodes can later learn its externally-observed endpoint (see
+/// `GetBeacons`/`Beacons` below).  A relay that accepts this overwrites `addrbytes`/`port` with
+/// the source address it actually observed the handshake on before storing/forwarding it --
+/// that's the NAT-mapped address a third party would need to dial, not whatever the publishing
+/// node believes its own address is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BeaconData {
+    pub addrbytes: PeerAddress,
+    pub port: u16,
+    pub public_key_hash: Hash160,
+    pub nonce: u64,
+    pub expire_block_height: u64
+}
+
+pub const BEACON_DATA_ENCODED_SIZE: u32 =
+    PEER_ADDRESS_ENCODED_SIZE +
+    2 +
+    HASH160_ENCODED_SIZE +
+    8 +
+    8;
+
+/// How many burn blocks a published `BeaconData` remains valid for before a relay drops it.
+/// Kept short so a stale NAT mapping doesn't get handed out as though it were still live.
+pub const BEACON_TTL_BLOCKS: u64 = 6;
+
+impl BeaconData {
+    /// Has this beacon aged out, given the burn block height a relay is currently at?
+    pub fn is_expired(&self, burn_block_height: u64) -> bool {
+        burn_block_height >= self.expire_block_height
+    }
+}
+
+/// Ask a relay for the beacons it's holding for the given public key hashes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GetBeacons {
+    pub public_key_hashes: Vec<Hash160>
+}
+
+/// A relay's reply to `GetBeacons`: the subset of the requested beacons it still has live (i.e.
+/// not yet past `BEACON_TTL_BLOCKS`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct BeaconsData {
+    pub beacons: Vec<BeaconData>
+}
+
+/// Per-source-address rate limit a relay applies to incoming beacon publications, so the relay
+/// set can't be turned into a reflection/amplification vector by a source flooding beacons for
+/// many different public key hashes.
+pub struct BeaconRateLimiter {
+    max_per_window: u32,
+    window_secs: u64,
+    seen: HashMap<PeerAddress, (u64, u32)>
+}
+
+impl BeaconRateLimiter {
+    pub fn new(max_per_window: u32, window_secs: u64) -> BeaconRateLimiter {
+        BeaconRateLimiter {
+            max_per_window: max_per_window,
+            window_secs: window_secs,
+            seen: HashMap::new()
+        }
+    }
+
+    /// Returns true if a beacon published from `source` at time `now` should be accepted,
+    /// recording it against that source's budget if so.
+    pub fn allow(&mut self, source: &PeerAddress, now: u64) -> bool {
+        let entry = self.seen.entry(source.clone()).or_insert((now, 0));
+        if now.saturating_sub(entry.0) >= self.window_secs {
+            *entry = (now, 0);
+        }
+        if entry.1 >= self.max_per_window {
+            return false;
+        }
+        entry.1 += 1;
+        true
+    }
+}
+
+/// Ask a peer which recent mempool transactions it holds, scoped to a window of burn blocks
+/// starting at `cursor` so a newly-connected peer can converge its mempool quickly instead of
+/// waiting on organic relay.  Lighter-weight than a full `MemPoolSyncData` reconciliation, which
+/// targets the whole mempool rather than a narrow recency window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GetMemTxInv {
+    pub cursor: u64,                  // burn block height the window starts at
+    pub num_blocks: u16                // width of the window, in burn blocks (not to exceed MAX_MEM_TX_INV_BLOCKS)
+}
+
+/// Maximum width, in burn blocks, of a single `GetMemTxInv` window.
+pub const MAX_MEM_TX_INV_BLOCKS: u16 = 256;
+
+/// Reply to `GetMemTxInv`: every txid the replying peer holds within the requested window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemTxInv {
+    pub txids: Vec<Txid>
+}
+
+/// Pull the actual transaction bodies for a batch of txids a `GetMemTxInv` round found missing.
+/// Bounded by `MAX_MEM_TX_BATCH_LEN`, the same way `MAX_MICROBLOCKS_UNCONFIRMED` bounds a
+/// microblock stream, so a single request can't force an unbounded reply.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GetMemTx {
+    pub txids: Vec<Txid>
+}
+
+/// Maximum number of transactions returned in a single `MemTx` reply.  A requester still
+/// missing transactions after this must issue another round of `GetMemTx`.
+pub const MAX_MEM_TX_BATCH_LEN: usize = 100;
+
+/// Reply to `GetMemTx`: the subset of requested transactions the peer actually had on hand.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemTx {
+    pub txs: Vec<StacksTransaction>
+}
+
 /// All P2P message types
 #[derive(Debug, Clone, PartialEq)]
 pub enum StacksMessageType {
@@ -684,10 +1469,59 @@ pub enum StacksMessageType {
     Nack(NackData),
     Ping(PingData),
     Pong(PongData),
-    // TODO: get unconfirmed transactions
+    PublishBeacon(BeaconData),
+    GetBeacons(GetBeacons),
+    Beacons(BeaconsData),
+    // GetMemTxInv/MemTxInv/GetMemTx/MemTx let a peer converge its mempool over this P2P relay
+    // protocol; GetMemPoolTxs (HttpRequestType) remains the bulk-sync path over HTTP RPC.
+    GetMemTxInv(GetMemTxInv),
+    MemTxInv(MemTxInv),
+    GetMemTx(GetMemTx),
+    MemTx(MemTx),
+    GetCompactFilters(GetCompactFilters),
+    CompactFilters(CompactFiltersData),
     // TODO: get unconfirmed microblocks
 }
 
+/// Salted per-process hash used to redact an address's bytes in logs (see `Redact`).  Generated
+/// once, on first use, so the same peer hashes the same way across every log line from this run
+/// but isn't correlatable across a node restart or against another node's logs.
+static REDACTION_SALT: AtomicU64 = AtomicU64::new(0);
+static REDACTION_SALT_INIT: Once = Once::new();
+
+fn redaction_salt() -> u64 {
+    REDACTION_SALT_INIT.call_once(|| {
+        REDACTION_SALT.store(thread_rng().next_u64(), Ordering::Relaxed);
+    });
+    REDACTION_SALT.load(Ordering::Relaxed)
+}
+
+fn redacted_addr_string(addr_bytes: &[u8], port: u16) -> String {
+    let mut hasher = DefaultHasher::new();
+    redaction_salt().hash(&mut hasher);
+    addr_bytes.hash(&mut hasher);
+    format!("[redacted:{:08x}]:{}", (hasher.finish() & 0xffff_ffff) as u32, port)
+}
+
+/// An opt-in redacted view of a value whose `Display`/`Debug` would otherwise print a raw IP
+/// address.  Wrap a value with `.redacted()` before logging it to emit only the port plus a
+/// stable salted hash of the address bytes instead of the address itself; the wrapped value's
+/// own `Display`/`Debug` are unaffected; networking code that actually needs to connect keeps
+/// using the real `PeerAddress`/`SocketAddr` directly.  See `TestPeerNetwork::dump_frontiers` for
+/// a real call site within this file.  Wiring this up to an opt-in flag on `ConnectionOptions`
+/// (so every production logging call site can decide whether to call `.redacted()`) belongs in
+/// `net::connection`, which isn't part of this snapshot.
+pub struct Redacted<'a, T>(&'a T);
+
+pub trait Redact {
+    fn redacted(&self) -> Redacted<Self> where Self: Sized {
+        Redacted(self)
+    }
+}
+
+impl Redact for NeighborKey {}
+impl Redact for PeerHost {}
+
 /// Peer address variants
 #[derive(Clone, PartialEq)]
 pub enum PeerHost {
@@ -713,6 +1547,25 @@ impl fmt::Debug for PeerHost {
     }
 }
 
+impl<'a> fmt::Display for Redacted<'a, PeerHost> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            // hostnames aren't raw IP addresses, so there's nothing to redact here
+            PeerHost::DNS(ref s, ref p) => write!(f, "{}:{}", s, p),
+            PeerHost::IP(ref a, ref p) => write!(f, "{}", redacted_addr_string(a.as_bytes(), *p))
+        }
+    }
+}
+
+impl<'a> fmt::Debug for Redacted<'a, PeerHost> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            PeerHost::DNS(ref s, ref p) => write!(f, "PeerHost::DNS({},{})", s, p),
+            PeerHost::IP(ref a, ref p) => write!(f, "PeerHost::IP({})", redacted_addr_string(a.as_bytes(), *p))
+        }
+    }
+}
+
 impl Hash for PeerHost {
     fn hash<H: Hasher>(&self, state: &mut H) {
         match *self {
@@ -849,7 +1702,13 @@ pub enum HttpRequestType {
     GetMicroblocksIndexed(HttpRequestMetadata, BlockHeaderHash),
     GetMicroblocksConfirmed(HttpRequestMetadata, BlockHeaderHash),
     GetMicroblocksUnconfirmed(HttpRequestMetadata, BlockHeaderHash, u16),
-    PostTransaction(HttpRequestMetadata, StacksTransaction)
+    PostTransaction(HttpRequestMetadata, StacksTransaction),
+    /// Ask a peer for the next page of its mempool transactions that `MemPoolSyncData`
+    /// indicates the requester doesn't already have, picking up just after `last_txid` in the
+    /// peer's `randomized_txids` order (or `Txid([0; 32])` to start from the beginning).  The
+    /// requester replays this with the last txid it received as the new cursor until the peer's
+    /// response reports no more (see `MemPoolDB::stream_txs`).
+    GetMemPoolTxs(HttpRequestMetadata, MemPoolSyncData, Txid)
 }
 
 /// The fields that Actually Matter to http responses
@@ -900,6 +1759,9 @@ pub enum HttpResponseType {
     Microblocks(HttpResponseMetadata, Vec<StacksMicroblock>),
     MicroblockStream(HttpResponseMetadata),
     TransactionID(HttpResponseMetadata, Txid),
+    /// Streamed reply to `GetMemPoolTxs`, written out transaction-by-transaction via
+    /// `MemPoolDB::stream_txs` rather than buffered into this variant.
+    MemPoolTxStream(HttpResponseMetadata),
     
     // peer-given error responses
     BadRequest(HttpResponseMetadata, String),
@@ -934,6 +1796,15 @@ pub enum StacksMessageID {
     Nack = 10,
     Ping = 11,
     Pong = 12,
+    PublishBeacon = 13,
+    GetBeacons = 14,
+    Beacons = 15,
+    GetMemTxInv = 16,
+    MemTxInv = 17,
+    GetMemTx = 18,
+    MemTx = 19,
+    GetCompactFilters = 20,
+    CompactFilters = 21,
     Reserved = 255
 }
 
@@ -965,13 +1836,103 @@ pub trait MessageSequence {
     fn get_message_name(&self) -> &'static str;
 }
 
+/// Maximum number of not-yet-fully-written frames an `OutboundQueue` will hold at once.
+pub const MAX_OUTBOUND_QUEUE_DEPTH: usize = 1024;
+
+/// Maximum total bytes (across all queued frames) an `OutboundQueue` will buffer at once.  Chosen
+/// to comfortably hold a handful of `MAX_MESSAGE_LEN`-sized replies without letting a slow or
+/// malicious peer force us to buffer an unbounded amount of unsent data.
+pub const MAX_OUTBOUND_QUEUE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Result of a `drain_writes()` call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteStatus {
+    /// The socket accepted as many bytes as it would take (i.e. it would now block); frames remain queued.
+    Ongoing,
+    /// Every queued frame has been fully written out.
+    Complete,
+}
+
+/// One serialized, not-yet-fully-written outbound message.  `request_id` is carried over from the
+/// `MessageSequence` that produced it purely so a caller can match a later `WouldBlock` back to
+/// the request it's still waiting to flush; `OutboundQueue` itself doesn't interpret it.
+struct OutboundFrame {
+    bytes: Vec<u8>,
+    written: usize,
+    request_id: u32,
+}
+
+/// A bounded queue of serialized, not-yet-fully-written frames for a single connection.  Lets
+/// `ProtocolFamily::write_message` hand off a frame and return immediately instead of blocking a
+/// non-blocking `mio` socket on a partial write; `drain_writes()` is then called (e.g. whenever
+/// the socket is writable) to push as many queued bytes as the socket will currently accept.
+/// Bounded by `MAX_OUTBOUND_QUEUE_DEPTH` and `MAX_OUTBOUND_QUEUE_BYTES` so a slow or malicious peer
+/// makes `enqueue()` fail with a recoverable `Error::OutboundQueueFull` instead of growing without end.
+pub struct OutboundQueue {
+    frames: VecDeque<OutboundFrame>,
+    buffered_bytes: usize,
+}
+
+impl OutboundQueue {
+    pub fn new() -> OutboundQueue {
+        OutboundQueue { frames: VecDeque::new(), buffered_bytes: 0 }
+    }
+
+    /// Queue up a serialized message for sending, tagged with the `MessageSequence::request_id`
+    /// that produced it.  Fails with `Error::OutboundQueueFull` if the queue is already at
+    /// `MAX_OUTBOUND_QUEUE_DEPTH` frames or `MAX_OUTBOUND_QUEUE_BYTES` buffered bytes.
+    pub fn enqueue(&mut self, bytes: Vec<u8>, request_id: u32) -> Result<(), Error> {
+        if self.frames.len() >= MAX_OUTBOUND_QUEUE_DEPTH || self.buffered_bytes + bytes.len() > MAX_OUTBOUND_QUEUE_BYTES {
+            return Err(Error::OutboundQueueFull);
+        }
+        self.buffered_bytes += bytes.len();
+        self.frames.push_back(OutboundFrame { bytes: bytes, written: 0, request_id: request_id });
+        Ok(())
+    }
+
+    pub fn depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn buffered_bytes(&self) -> usize {
+        self.buffered_bytes
+    }
+
+    /// Write as many queued bytes as `fd` will currently accept, oldest frame first.  Returns
+    /// `Ok(WriteStatus::Ongoing)` as soon as `fd` returns `WouldBlock` (frames remain queued, to be
+    /// finished on a later call), or `Ok(WriteStatus::Complete)` once the queue is empty.
+    pub fn drain_writes<W: Write>(&mut self, fd: &mut W) -> Result<WriteStatus, Error> {
+        while let Some(frame) = self.frames.front_mut() {
+            match fd.write(&frame.bytes[frame.written..]) {
+                Ok(0) => {
+                    return Err(Error::WriteError(io::Error::from(io::ErrorKind::WriteZero)));
+                },
+                Ok(sz) => {
+                    frame.written += sz;
+                    if frame.written >= frame.bytes.len() {
+                        self.buffered_bytes -= frame.bytes.len();
+                        self.frames.pop_front();
+                    }
+                },
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    return Ok(WriteStatus::Ongoing);
+                },
+                Err(e) => {
+                    return Err(Error::WriteError(e));
+                }
+            }
+        }
+        Ok(WriteStatus::Complete)
+    }
+}
+
 pub trait ProtocolFamily {
     type Preamble: StacksMessageCodec + Send + Sync + Clone + PartialEq + std::fmt::Debug;
     type Message : MessageSequence + Send + Sync + Clone + PartialEq + std::fmt::Debug;
 
     /// Return the maximum possible length of the serialized Preamble type
     fn preamble_size_hint(&mut self) -> usize;
-    
+
     /// Determine how long the message payload will be, given the Preamble (may return None if the
     /// payload length cannot be determined solely by the Preamble).
     fn payload_len(&mut self, preamble: &Self::Preamble) -> Option<usize>;
@@ -985,7 +1946,7 @@ pub trait ProtocolFamily {
     /// buf slice is guaranteed to have at least `payload_len()` bytes if `payload_len()` returns
     /// Some(...).
     fn read_payload(&mut self, preamble: &Self::Preamble, buf: &[u8]) -> Result<(Self::Message, usize), Error>;
-    
+
     /// Given a preamble and a Read, attempt to stream a message.  This will be called if
     /// `payload_len()` returns None.  This method will be repeatedly called with new data until a
     /// message can be obtained; therefore, the ProtocolFamily implementation will need to do its
@@ -996,15 +1957,384 @@ pub trait ProtocolFamily {
     /// authenticity.  Not all protocols need to do this.
     fn verify_payload_bytes(&mut self, key: &StacksPublicKey, preamble: &Self::Preamble, bytes: &[u8]) -> Result<(), Error>;
 
-    /// Given a Write and a Message, write it out.  This method is also responsible for generating
-    /// and writing out a Preamble for its Message.
+    /// Serialize a Preamble and Message and enqueue the resulting frame onto this protocol's
+    /// `OutboundQueue` (tagged with `message.request_id()`), rather than writing to `fd` directly.
+    /// Returns `Error::OutboundQueueFull` if the queue is already saturated.  Call `drain_writes`
+    /// -- as many times as it takes -- to actually push the queued bytes out over `fd`.
+    ///
+    /// `fd` is threaded through for implementors that haven't migrated to queuing yet and still
+    /// write synchronously; once an implementor adopts an `OutboundQueue`, its `write_message` can
+    /// ignore `fd` entirely and push bytes out of `drain_writes` instead.
     fn write_message<W: Write>(&mut self, fd: &mut W, message: &Self::Message) -> Result<(), Error>;
+
+    /// Push as many bytes of already-enqueued messages out over `fd` as it will currently accept.
+    /// See `OutboundQueue::drain_writes`.  Defaults to a no-op over an empty queue so that adding
+    /// this method doesn't break existing `ProtocolFamily` implementors (e.g. `StacksP2P` in
+    /// `net::chat`) that haven't yet migrated `write_message` to enqueue through an
+    /// `OutboundQueue` -- override it once that migration happens.
+    fn drain_writes<W: Write>(&mut self, _fd: &mut W) -> Result<WriteStatus, Error> {
+        Ok(WriteStatus::Complete)
+    }
 }
 
-// these implement the ProtocolFamily trait 
+// these implement the ProtocolFamily trait
 #[derive(Debug, Clone, PartialEq)]
 pub struct StacksP2P {}
 
+/// `Noise_XX_25519_ChaChaPoly_SHA256`.  Used verbatim as the Noise protocol name input to
+/// `NoiseSymmetricState::initialize_symmetric` (see the Noise Protocol Framework spec, section 3).
+const NOISE_PROTOCOL_NAME: &[u8] = b"Noise_XX_25519_ChaChaPoly_SHA256";
+
+fn noise_sha256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    let digest = hasher.finalize();
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&digest);
+    out
+}
+
+/// `HKDF(chaining_key, input_key_material, num_outputs)` as defined by the Noise spec: an
+/// RFC 5869 HKDF-Extract using `chaining_key` as salt, then HKDF-Expand with an empty info string
+/// for `num_outputs * 32` bytes.
+fn noise_hkdf(chaining_key: &[u8; 32], input_key_material: &[u8], okm: &mut [u8]) {
+    let hk = Hkdf::<Sha256>::new(Some(chaining_key), input_key_material);
+    hk.expand(&[], okm).expect("okm length must be <= 255 * HASHLEN for HKDF-SHA256");
+}
+
+fn noise_nonce_bytes(n: u64) -> [u8; 12] {
+    // Noise encodes the nonce as 4 zero bytes followed by a little-endian 8-byte counter.
+    let mut nonce = [0u8; 12];
+    nonce[4..].copy_from_slice(&n.to_le_bytes());
+    nonce
+}
+
+/// The running `(chaining_key, hash, cipher_key, nonce)` state threaded through a Noise
+/// handshake.  See the Noise Protocol Framework spec, section 5.2 ("The SymmetricState object").
+struct NoiseSymmetricState {
+    ck: [u8; 32],
+    h: [u8; 32],
+    k: Option<[u8; 32]>,
+    n: u64,
+}
+
+impl NoiseSymmetricState {
+    fn initialize_symmetric(protocol_name: &[u8]) -> NoiseSymmetricState {
+        let h = if protocol_name.len() <= 32 {
+            let mut padded = [0u8; 32];
+            padded[..protocol_name.len()].copy_from_slice(protocol_name);
+            padded
+        } else {
+            noise_sha256(protocol_name)
+        };
+        NoiseSymmetricState { ck: h, h: h, k: None, n: 0 }
+    }
+
+    fn mix_hash(&mut self, data: &[u8]) {
+        let mut preimage = Vec::with_capacity(32 + data.len());
+        preimage.extend_from_slice(&self.h);
+        preimage.extend_from_slice(data);
+        self.h = noise_sha256(&preimage);
+    }
+
+    fn mix_key(&mut self, input_key_material: &[u8]) {
+        let mut okm = [0u8; 64];
+        noise_hkdf(&self.ck, input_key_material, &mut okm);
+        self.ck.copy_from_slice(&okm[..32]);
+        self.k = Some({
+            let mut k = [0u8; 32];
+            k.copy_from_slice(&okm[32..]);
+            k
+        });
+        self.n = 0;
+    }
+
+    /// Encrypt `plaintext` (if a cipher key has been mixed in yet; otherwise pass it through
+    /// unchanged) under the running hash as associated data, then fold the result into the hash
+    /// so later messages are bound to everything sent so far.
+    fn encrypt_and_hash(&mut self, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        let ciphertext = match self.k {
+            Some(ref k) => {
+                let cipher = ChaCha20Poly1305::new(k.into());
+                let nonce = noise_nonce_bytes(self.n);
+                let out = cipher.encrypt(&nonce.into(), Payload { msg: plaintext, aad: &self.h })
+                    .map_err(|_| Error::InvalidHandshake)?;
+                self.n += 1;
+                out
+            },
+            None => plaintext.to_vec(),
+        };
+        self.mix_hash(&ciphertext);
+        Ok(ciphertext)
+    }
+
+    fn decrypt_and_hash(&mut self, ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        let plaintext = match self.k {
+            Some(ref k) => {
+                let cipher = ChaCha20Poly1305::new(k.into());
+                let nonce = noise_nonce_bytes(self.n);
+                let out = cipher.decrypt(&nonce.into(), Payload { msg: ciphertext, aad: &self.h })
+                    .map_err(|_| Error::InvalidHandshake)?;
+                self.n += 1;
+                out
+            },
+            None => ciphertext.to_vec(),
+        };
+        self.mix_hash(ciphertext);
+        Ok(plaintext)
+    }
+
+    /// `Split()`: once the handshake's final message has been processed, derive the pair of
+    /// one-way transport keys from the final chaining key.
+    fn split(&self) -> ([u8; 32], [u8; 32]) {
+        let mut okm = [0u8; 64];
+        noise_hkdf(&self.ck, &[], &mut okm);
+        let mut k1 = [0u8; 32];
+        let mut k2 = [0u8; 32];
+        k1.copy_from_slice(&okm[..32]);
+        k2.copy_from_slice(&okm[32..]);
+        (k1, k2)
+    }
+}
+
+/// A Curve25519 keypair used as a Noise `s` (static) or `e` (ephemeral) key.
+pub struct NoiseKeypair {
+    secret: X25519StaticSecret,
+    public: X25519PublicKey,
+}
+
+impl NoiseKeypair {
+    pub fn generate() -> NoiseKeypair {
+        let secret = X25519StaticSecret::new(&mut thread_rng());
+        let public = X25519PublicKey::from(&secret);
+        NoiseKeypair { secret: secret, public: public }
+    }
+
+    pub fn public_bytes(&self) -> [u8; 32] {
+        self.public.to_bytes()
+    }
+}
+
+fn noise_dh(secret: &X25519StaticSecret, public_bytes: &[u8; 32]) -> [u8; 32] {
+    let public = X25519PublicKey::from(*public_bytes);
+    secret.diffie_hellman(&public).to_bytes()
+}
+
+/// A single-direction ChaCha20-Poly1305 cipher plus its monotonic nonce counter, as derived by
+/// `NoiseHandshake::finish`'s call to `NoiseSymmetricState::split`.
+struct NoiseCipherState {
+    cipher: ChaCha20Poly1305,
+    nonce: u64,
+}
+
+impl NoiseCipherState {
+    fn new(key: [u8; 32]) -> NoiseCipherState {
+        NoiseCipherState { cipher: ChaCha20Poly1305::new((&key).into()), nonce: 0 }
+    }
+
+    fn encrypt(&mut self, plaintext: &[u8]) -> Vec<u8> {
+        let nonce = noise_nonce_bytes(self.nonce);
+        let ciphertext = self.cipher.encrypt(&nonce.into(), plaintext)
+            .expect("ChaCha20-Poly1305 encryption with a fresh nonce cannot fail");
+        self.nonce += 1;
+        ciphertext
+    }
+
+    fn decrypt(&mut self, ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        let nonce = noise_nonce_bytes(self.nonce);
+        let plaintext = self.cipher.decrypt(&nonce.into(), ciphertext)
+            .map_err(|_| Error::InvalidMessage)?;
+        self.nonce += 1;
+        Ok(plaintext)
+    }
+}
+
+/// The pair of one-way cipher states produced once a `NoiseHandshake` completes, used to
+/// encrypt/decrypt the `StacksMessage` record stream for the lifetime of the connection.
+pub struct NoiseTransportKeys {
+    send: NoiseCipherState,
+    recv: NoiseCipherState,
+}
+
+impl NoiseTransportKeys {
+    pub fn encrypt_record(&mut self, plaintext: &[u8]) -> Vec<u8> {
+        self.send.encrypt(plaintext)
+    }
+
+    pub fn decrypt_record(&mut self, ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        self.recv.decrypt(ciphertext)
+    }
+}
+
+/// Drives a Noise `XX` handshake (`-> e`, `<- e, ee, s, es`, `-> s, se`) to mutual authentication
+/// over Curve25519 static/ephemeral keypairs, ending with both sides holding the peer's real
+/// static public key (see `finish`) and a pair of `NoiseTransportKeys` for the record layer.
+/// Binding the learned static key to `NeighborKey`/`Neighbor::public_key` -- so the post-handshake
+/// identity matches what the peer advertised over the (still-unencrypted) application layer --
+/// is the caller's responsibility once `finish()` returns.
+pub struct NoiseHandshake {
+    initiator: bool,
+    symmetric: NoiseSymmetricState,
+    s: NoiseKeypair,
+    e: Option<NoiseKeypair>,
+    rs: Option<[u8; 32]>,
+    re: Option<[u8; 32]>,
+}
+
+impl NoiseHandshake {
+    fn new(initiator: bool, s: NoiseKeypair, prologue: &[u8]) -> NoiseHandshake {
+        let mut symmetric = NoiseSymmetricState::initialize_symmetric(NOISE_PROTOCOL_NAME);
+        symmetric.mix_hash(prologue);
+        NoiseHandshake { initiator: initiator, symmetric: symmetric, s: s, e: None, rs: None, re: None }
+    }
+
+    pub fn new_initiator(s: NoiseKeypair, prologue: &[u8]) -> NoiseHandshake {
+        NoiseHandshake::new(true, s, prologue)
+    }
+
+    pub fn new_responder(s: NoiseKeypair, prologue: &[u8]) -> NoiseHandshake {
+        NoiseHandshake::new(false, s, prologue)
+    }
+
+    /// `-> e`: called by the initiator to produce the first handshake message.
+    pub fn write_message_1(&mut self) -> Vec<u8> {
+        let e = NoiseKeypair::generate();
+        self.symmetric.mix_hash(&e.public_bytes());
+        let msg = e.public_bytes().to_vec();
+        self.e = Some(e);
+        msg
+    }
+
+    /// `-> e`: called by the responder to consume the first handshake message.
+    pub fn read_message_1(&mut self, msg: &[u8]) -> Result<(), Error> {
+        if msg.len() != 32 {
+            return Err(Error::InvalidHandshake);
+        }
+        let mut re = [0u8; 32];
+        re.copy_from_slice(msg);
+        self.symmetric.mix_hash(&re);
+        self.re = Some(re);
+        Ok(())
+    }
+
+    /// `<- e, ee, s, es`: called by the responder to produce the second handshake message.
+    pub fn write_message_2(&mut self) -> Result<Vec<u8>, Error> {
+        let re = self.re.ok_or(Error::InvalidHandshake)?;
+
+        let e = NoiseKeypair::generate();
+        self.symmetric.mix_hash(&e.public_bytes());
+        self.symmetric.mix_key(&noise_dh(&e.secret, &re));
+
+        let encrypted_s = self.symmetric.encrypt_and_hash(&self.s.public_bytes())?;
+        self.symmetric.mix_key(&noise_dh(&self.s.secret, &re));
+
+        let mut out = Vec::with_capacity(32 + encrypted_s.len());
+        out.extend_from_slice(&e.public_bytes());
+        out.extend_from_slice(&encrypted_s);
+        self.e = Some(e);
+        Ok(out)
+    }
+
+    /// `<- e, ee, s, es`: called by the initiator to consume the second handshake message.
+    pub fn read_message_2(&mut self, msg: &[u8]) -> Result<(), Error> {
+        if msg.len() < 32 {
+            return Err(Error::InvalidHandshake);
+        }
+        let (re_bytes, rest) = msg.split_at(32);
+        let mut re = [0u8; 32];
+        re.copy_from_slice(re_bytes);
+        self.symmetric.mix_hash(&re);
+
+        let e = self.e.as_ref().ok_or(Error::InvalidHandshake)?;
+        self.symmetric.mix_key(&noise_dh(&e.secret, &re));
+
+        let rs_bytes = self.symmetric.decrypt_and_hash(rest)?;
+        if rs_bytes.len() != 32 {
+            return Err(Error::InvalidHandshake);
+        }
+        let mut rs = [0u8; 32];
+        rs.copy_from_slice(&rs_bytes);
+        self.symmetric.mix_key(&noise_dh(&e.secret, &rs));
+
+        self.re = Some(re);
+        self.rs = Some(rs);
+        Ok(())
+    }
+
+    /// `-> s, se`: called by the initiator to produce the third handshake message.
+    pub fn write_message_3(&mut self) -> Result<Vec<u8>, Error> {
+        let re = self.re.ok_or(Error::InvalidHandshake)?;
+        let encrypted_s = self.symmetric.encrypt_and_hash(&self.s.public_bytes())?;
+        self.symmetric.mix_key(&noise_dh(&self.s.secret, &re));
+        Ok(encrypted_s)
+    }
+
+    /// `-> s, se`: called by the responder to consume the third handshake message.
+    pub fn read_message_3(&mut self, msg: &[u8]) -> Result<(), Error> {
+        let rs_bytes = self.symmetric.decrypt_and_hash(msg)?;
+        if rs_bytes.len() != 32 {
+            return Err(Error::InvalidHandshake);
+        }
+        let mut rs = [0u8; 32];
+        rs.copy_from_slice(&rs_bytes);
+
+        let e = self.e.as_ref().ok_or(Error::InvalidHandshake)?;
+        self.symmetric.mix_key(&noise_dh(&e.secret, &rs));
+
+        self.rs = Some(rs);
+        Ok(())
+    }
+
+    /// Complete the handshake and derive the pair of transport cipher keys via `Split()`.
+    /// Returns the peer's real static public key (for the caller to bind against
+    /// `NeighborKey`/`Neighbor::public_key`) along with `NoiseTransportKeys` already oriented so
+    /// the caller's `encrypt_record` always sends and `decrypt_record` always receives,
+    /// regardless of which side was the initiator.
+    pub fn finish(self) -> Result<([u8; 32], NoiseTransportKeys), Error> {
+        let rs = self.rs.ok_or(Error::InvalidHandshake)?;
+        let (k1, k2) = self.symmetric.split();
+        let (send_key, recv_key) = if self.initiator { (k1, k2) } else { (k2, k1) };
+        Ok((rs, NoiseTransportKeys {
+            send: NoiseCipherState::new(send_key),
+            recv: NoiseCipherState::new(recv_key),
+        }))
+    }
+}
+
+/// Would wrap `StacksP2P` in a `ProtocolFamily` that runs a `NoiseHandshake` before any
+/// `StacksMessage` flows, then routes `read_preamble`/`stream_payload` through
+/// `NoiseTransportKeys::decrypt_record` and `write_message`/`drain_writes` through
+/// `NoiseTransportKeys::encrypt_record` before delegating to `inner`.  The cryptographic core
+/// above (`NoiseHandshake`, `NoiseTransportKeys`) is complete, self-contained, and exercised by
+/// `noise_handshake_round_trip` below.  What's intentionally not implemented is the
+/// `impl ProtocolFamily for NoiseStacksP2P` itself: that requires a concrete `Message` type
+/// implementing `MessageSequence` to hand back from `read_payload`/`stream_payload`, and
+/// `StacksP2P`'s own `impl ProtocolFamily` (which would supply one) lives in `net::chat`, which
+/// isn't part of this snapshot -- there is nothing here yet for a wrapper to wrap.
+pub struct NoiseStacksP2P {
+    inner: StacksP2P,
+    transport_keys: Option<NoiseTransportKeys>,
+    remote_static_pubkey: Option<[u8; 32]>,
+}
+
+impl NoiseStacksP2P {
+    pub fn new(inner: StacksP2P) -> NoiseStacksP2P {
+        NoiseStacksP2P { inner: inner, transport_keys: None, remote_static_pubkey: None }
+    }
+
+    /// Install the transport keys and remote static key produced by a completed `NoiseHandshake`.
+    pub fn complete_handshake(&mut self, handshake: NoiseHandshake) -> Result<(), Error> {
+        let (remote_static_pubkey, transport_keys) = handshake.finish()?;
+        self.remote_static_pubkey = Some(remote_static_pubkey);
+        self.transport_keys = Some(transport_keys);
+        Ok(())
+    }
+
+    pub fn remote_static_pubkey(&self) -> Option<[u8; 32]> {
+        self.remote_static_pubkey
+    }
+}
+
 pub use self::http::StacksHttp;
 
 // an array in our protocol can't exceed this many items
@@ -1088,6 +2418,20 @@ impl fmt::Debug for NeighborKey {
     }
 }
 
+impl<'a> fmt::Display for Redacted<'a, NeighborKey> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let peer_version_str = if self.0.peer_version > 0 { format!("{:08x}", self.0.peer_version) } else { "UNKNOWN".to_string() };
+        let network_id_str = if self.0.network_id > 0 { format!("{:08x}", self.0.network_id) } else { "UNKNOWN".to_string() };
+        write!(f, "{}+{}://{}", peer_version_str, network_id_str, redacted_addr_string(self.0.addrbytes.as_bytes(), self.0.port))
+    }
+}
+
+impl<'a> fmt::Debug for Redacted<'a, NeighborKey> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
 impl NeighborKey {
     pub fn from_neighbor_address(peer_version: u32, network_id: u32, na: &NeighborAddress) -> NeighborKey {
         NeighborKey {
@@ -1117,6 +2461,8 @@ pub struct Neighbor {
 
     pub in_degree: u32,         // number of peers who list this peer as a neighbor
     pub out_degree: u32,        // number of neighbors this peer has
+
+    pub services: u64,          // intersection of our and this peer's advertised PeerServices, negotiated at handshake time
 }
 
 pub const NUM_NEIGHBORS : usize = 32;
@@ -1197,7 +2543,9 @@ mod test {
     use std::thread;
 
     use std::fs;
-    
+    use std::path::Path;
+    use std::path::PathBuf;
+
     use rand::RngCore;
     use rand;
 
@@ -1387,6 +2735,16 @@ mod test {
         pub blacklisted: i64,
         pub data_url: UrlString,
         pub test_name: String,
+        // hard-coded (height, burn header hash) pairs a test can use to simulate
+        // ConnectionOptions-level fork checkpoints (see check_fork_checkpoints); not wired into
+        // a live handshake here, since that lives in net::connection/net::chat.
+        pub fork_checkpoints: Vec<ForkCheckpoint>,
+        // overrides what this peer reports as its Preamble::rc_consensus_hash, so a test can put
+        // two peers in different simulated reward cycles (see reward_cycle_compatibility)
+        pub simulated_rc_consensus_hash: Option<ConsensusHash>,
+        // selects whether PeerDB/BurnDB live on disk (under /tmp) or in SQLite's :memory: (see
+        // TestDbBackend); StacksChainState's MARF index is always disk-backed regardless.
+        pub db_backend: TestDbBackend,
     }
 
     impl TestPeerConfig {
@@ -1411,7 +2769,10 @@ mod test {
                 whitelisted: 0,
                 blacklisted: 0,
                 data_url: "".into(),
-                test_name: "".into()
+                test_name: "".into(),
+                fork_checkpoints: vec![],
+                simulated_rc_consensus_hash: None,
+                db_backend: TestDbBackend::Disk
             }
         }
 
@@ -1440,6 +2801,14 @@ mod test {
             self.initial_neighbors.push(n.clone());
         }
 
+        pub fn add_fork_checkpoint(&mut self, burn_block_height: u64, burn_header_hash: BurnchainHeaderHash) -> () {
+            self.fork_checkpoints.push(ForkCheckpoint { burn_block_height: burn_block_height, burn_header_hash: burn_header_hash });
+        }
+
+        pub fn set_simulated_rc_consensus_hash(&mut self, rc_consensus_hash: ConsensusHash) -> () {
+            self.simulated_rc_consensus_hash = Some(rc_consensus_hash);
+        }
+
         pub fn to_neighbor(&self) -> Neighbor {
             Neighbor {
                 addr: NeighborKey {
@@ -1458,7 +2827,8 @@ mod test {
                 asn: self.asn,
                 org: self.org,
                 in_degree: 0,
-                out_degree: 0
+                out_degree: 0,
+                services: PeerServices::RELAY
             }
         }
 
@@ -1467,7 +2837,34 @@ mod test {
         }
     }
 
+    /// Where a `TestPeer`'s (or `make_test_chain_dbs`'s) databases live.  `InMemory` backs
+    /// `PeerDB`/`BurnDB` with SQLite's `:memory:` pseudo-path instead of a `/tmp` directory, so
+    /// parallel test runs don't collide or leak directories on disk.  `StacksChainState`'s MARF
+    /// index still needs a real backing file regardless of this setting -- an in-RAM MARF is a
+    /// larger undertaking than this snapshot's `net` crate touches -- so chainstate is always
+    /// disk-backed for now; see `TestPeer::make_default_tenure`'s second `StacksChainState::open`,
+    /// which reopens that same on-disk directory and therefore already sees the same state
+    /// without needing any extra shared-handle plumbing.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum TestDbBackend {
+        Disk,
+        InMemory,
+    }
+
+    impl TestDbBackend {
+        fn sqlite_path(&self, disk_path: &str) -> String {
+            match *self {
+                TestDbBackend::Disk => disk_path.to_string(),
+                TestDbBackend::InMemory => ":memory:".to_string(),
+            }
+        }
+    }
+
     pub fn make_test_chain_dbs(testname: &str, burnchain: &Burnchain, network_id: u32, key_expires: u64, data_url: UrlString, asn4_entries: &Vec<ASEntry4>, initial_neighbors: &Vec<Neighbor>) -> (PeerDB, BurnDB, StacksChainState) {
+        make_test_chain_dbs_with_backend(testname, burnchain, network_id, key_expires, data_url, asn4_entries, initial_neighbors, TestDbBackend::Disk)
+    }
+
+    pub fn make_test_chain_dbs_with_backend(testname: &str, burnchain: &Burnchain, network_id: u32, key_expires: u64, data_url: UrlString, asn4_entries: &Vec<ASEntry4>, initial_neighbors: &Vec<Neighbor>, backend: TestDbBackend) -> (PeerDB, BurnDB, StacksChainState) {
         let test_path = format!("/tmp/blockstack-test-databases-{}", testname);
         match fs::metadata(&test_path) {
             Ok(_) => {
@@ -1478,8 +2875,8 @@ mod test {
 
         fs::create_dir_all(&test_path).unwrap();
 
-        let burndb_path = format!("{}/burn", &test_path);
-        let peerdb_path = format!("{}/peers.db", &test_path);
+        let burndb_path = backend.sqlite_path(&format!("{}/burn", &test_path));
+        let peerdb_path = backend.sqlite_path(&format!("{}/peers.db", &test_path));
         let chainstate_path = format!("{}/chainstate", &test_path);
 
         let peerdb = PeerDB::connect(&peerdb_path, true, network_id, burnchain.network_id, key_expires, data_url.clone(), &asn4_entries, Some(&initial_neighbors)).unwrap();
@@ -1502,6 +2899,38 @@ mod test {
         thread_handle.join().unwrap();
     }
 
+    /// Size of each blob chunk in a `TestPeer` snapshot archive (see `TestPeer::dump_snapshot` /
+    /// `TestPeer::from_snapshot`).
+    pub const SNAPSHOT_BLOB_SIZE: usize = 4 * 1024 * 1024;
+
+    fn snapshot_walk_files(root: &Path, rel: &Path, out: &mut Vec<PathBuf>) {
+        let dir = root.join(rel);
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => return
+        };
+        for entry in entries {
+            let entry = entry.unwrap();
+            let entry_rel = rel.join(entry.file_name());
+            if entry.file_type().unwrap().is_dir() {
+                snapshot_walk_files(root, &entry_rel, out);
+            }
+            else {
+                out.push(entry_rel);
+            }
+        }
+    }
+
+    fn snapshot_blob_hash(bytes: &[u8]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn snapshot_blob_name(rel_path: &str, blob_index: usize) -> String {
+        format!("{}.{:06}", rel_path.replace("/", "_"), blob_index)
+    }
+
     pub struct TestPeer {
         pub config: TestPeerConfig,
         pub network: PeerNetwork,
@@ -1526,8 +2955,8 @@ mod test {
             let mut miner_factory = TestMinerFactory::new();
             let mut miner = miner_factory.next_miner(&config.burnchain, 1, 1, AddressHashMode::SerializeP2PKH);
 
-            let burndb_path = format!("{}/burn", &test_path);
-            let peerdb_path = format!("{}/peers.db", &test_path);
+            let burndb_path = config.db_backend.sqlite_path(&format!("{}/burn", &test_path));
+            let peerdb_path = config.db_backend.sqlite_path(&format!("{}/peers.db", &test_path));
             let chainstate_path = format!("{}/chainstate", &test_path);
 
             let mut peerdb = PeerDB::connect(&peerdb_path, true, config.network_id, config.burnchain.network_id, config.private_key_expire, config.data_url.clone(), &config.asn4_entries, Some(&config.initial_neighbors)).unwrap();
@@ -1586,6 +3015,158 @@ mod test {
             }
         }
 
+        fn test_path(config: &TestPeerConfig) -> String {
+            format!("/tmp/blockstack-test-peer-{}-{}", &config.test_name, config.server_port)
+        }
+
+        /// Archive this peer's on-disk PeerDB/BurnDB/StacksChainState test directory into a
+        /// portable snapshot at `dest_dir`, so a later `TestPeer::from_snapshot` can reconstruct
+        /// it without replaying the whole burnchain.  Every file under the test directory is
+        /// split into `SNAPSHOT_BLOB_SIZE` blobs, each hashed, with the (relative path, length,
+        /// blob hashes) recorded in a manifest so `from_snapshot` can detect a corrupted or
+        /// truncated archive up front instead of handing back a half-initialized peer.
+        pub fn dump_snapshot(&self, dest_dir: &str) -> io::Result<()> {
+            let test_path = TestPeer::test_path(&self.config);
+            let dest = Path::new(dest_dir);
+            let blobs_dir = dest.join("blobs");
+            fs::create_dir_all(&blobs_dir)?;
+
+            let mut files = vec![];
+            snapshot_walk_files(Path::new(&test_path), Path::new(""), &mut files);
+
+            let mut manifest = String::new();
+            manifest.push_str(&format!("{}\n", files.len()));
+
+            for rel in files.iter() {
+                let rel_str = rel.to_str().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "non-UTF8 path in test directory"))?;
+                let contents = fs::read(Path::new(&test_path).join(rel))?;
+
+                let mut hashes = vec![];
+                for (i, chunk) in contents.chunks(SNAPSHOT_BLOB_SIZE).enumerate() {
+                    hashes.push(format!("{:016x}", snapshot_blob_hash(chunk)));
+                    fs::write(blobs_dir.join(snapshot_blob_name(rel_str, i)), chunk)?;
+                }
+
+                manifest.push_str(&format!("{}\t{}\t{}\n", rel_str, contents.len(), hashes.join(",")));
+            }
+
+            fs::write(dest.join("manifest.txt"), manifest)
+        }
+
+        /// Reconstruct a `TestPeer`'s on-disk databases from an archive written by
+        /// `dump_snapshot`, instead of replaying the whole burnchain the way `TestPeer::new`
+        /// does.  Every blob is checked against its manifest hash before being written back out,
+        /// so a corrupted or truncated archive fails with an `io::Error` up front rather than
+        /// producing a half-initialized peer.
+        pub fn from_snapshot(config: TestPeerConfig, src_dir: &str) -> io::Result<TestPeer> {
+            if config.db_backend != TestDbBackend::Disk {
+                // A snapshot is an archive of on-disk files; restoring it into an `InMemory`
+                // config would write the blobs back out to disk and then open fresh, empty
+                // `":memory:"` databases on top of them, silently discarding everything we just
+                // restored.  Fail loudly instead of pretending this combination works.
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "from_snapshot requires TestDbBackend::Disk"));
+            }
+
+            let src = Path::new(src_dir);
+            let manifest = fs::read_to_string(src.join("manifest.txt"))?;
+            let mut lines = manifest.lines();
+
+            let num_files: usize = lines.next()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "empty snapshot manifest"))?
+                .parse()
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "corrupt snapshot manifest header"))?;
+
+            let test_path = TestPeer::test_path(&config);
+            match fs::metadata(&test_path) {
+                Ok(_) => { fs::remove_dir_all(&test_path)?; },
+                Err(_) => {}
+            };
+            fs::create_dir_all(&test_path)?;
+
+            let mut restored = 0;
+            for line in lines {
+                let mut parts = line.splitn(3, '\t');
+                let rel = parts.next().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "corrupt snapshot manifest entry"))?;
+                let total_len: usize = parts.next()
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "corrupt snapshot manifest entry"))?
+                    .parse()
+                    .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "corrupt snapshot manifest entry"))?;
+                let hash_list = parts.next().unwrap_or("");
+
+                let mut out = Vec::with_capacity(total_len);
+                for (i, hash_hex) in hash_list.split(',').filter(|s| !s.is_empty()).enumerate() {
+                    let chunk = fs::read(src.join("blobs").join(snapshot_blob_name(rel, i)))?;
+                    let expected = u64::from_str_radix(hash_hex, 16)
+                        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "corrupt snapshot blob hash"))?;
+                    if snapshot_blob_hash(&chunk) != expected {
+                        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("snapshot blob {} of {} failed its hash check", i, rel)));
+                    }
+                    out.extend_from_slice(&chunk);
+                }
+                if out.len() != total_len {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, format!("snapshot file {} is truncated", rel)));
+                }
+
+                let dest_path = Path::new(&test_path).join(rel);
+                if let Some(parent) = dest_path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&dest_path, &out)?;
+                restored += 1;
+            }
+
+            if restored != num_files {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "snapshot manifest file count doesn't match the files it lists"));
+            }
+
+            TestPeer::from_restored_path(config, test_path)
+        }
+
+        // re-opens a TestPeer's databases from an already-populated test directory (either
+        // restored by from_snapshot or left over from a prior run), skipping TestPeer::new's
+        // burnchain-replay loop entirely.
+        fn from_restored_path(config: TestPeerConfig, test_path: String) -> io::Result<TestPeer> {
+            let mut miner_factory = TestMinerFactory::new();
+            let miner = miner_factory.next_miner(&config.burnchain, 1, 1, AddressHashMode::SerializeP2PKH);
+
+            // Snapshots are captured from on-disk files, so restoring only makes sense for a
+            // disk-backed config; an InMemory config has nothing on disk to have been dumped.
+            let burndb_path = config.db_backend.sqlite_path(&format!("{}/burn", &test_path));
+            let peerdb_path = config.db_backend.sqlite_path(&format!("{}/peers.db", &test_path));
+            let chainstate_path = format!("{}/chainstate", &test_path);
+
+            let peerdb = PeerDB::connect(&peerdb_path, true, config.network_id, config.burnchain.network_id, config.private_key_expire, config.data_url.clone(), &config.asn4_entries, Some(&config.initial_neighbors))
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("failed to reopen restored PeerDB: {:?}", &e)))?;
+            let burndb = BurnDB::connect(&burndb_path, config.burnchain.first_block_height, &config.burnchain.first_block_hash, get_epoch_time_secs(), true)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("failed to reopen restored BurnDB: {:?}", &e)))?;
+            let chainstate = StacksChainState::open(false, config.network_id, &chainstate_path)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("failed to reopen restored chainstate: {:?}", &e)))?;
+            let stacks_node = TestStacksNode::from_chainstate(chainstate);
+
+            let local_peer = PeerDB::get_local_peer(peerdb.conn())
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("restored PeerDB is missing its local peer record: {:?}", &e)))?;
+
+            let burnchain_view = {
+                let mut tx = burndb.tx_begin().map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{:?}", &e)))?;
+                BurnDB::get_burnchain_view(&mut tx, &config.burnchain).map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{:?}", &e)))?
+            };
+
+            let local_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), config.server_port);
+            let http_local_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), config.http_port);
+
+            let mut peer_network = PeerNetwork::new(peerdb, local_peer, config.peer_version, config.burnchain.clone(), burnchain_view, config.connection_opts.clone());
+            peer_network.bind(&local_addr, &http_local_addr).map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{:?}", &e)))?;
+
+            Ok(TestPeer {
+                config: config,
+                network: peer_network,
+                burndb: Some(burndb),
+                miner: miner,
+                stacks_node: Some(stacks_node),
+                chainstate_path: chainstate_path
+            })
+        }
+
         pub fn connect_initial(&mut self) -> Result<(), net_error> {
             let local_peer = PeerDB::get_local_peer(self.network.peerdb.conn()).unwrap();
             let chain_view = match self.burndb {
@@ -1848,5 +3429,281 @@ mod test {
             info!("{:#?}", &peers);
             info!("--- END ALL PEERS ({}) -----", peers.len());
         }
+
+        /// Returns whether, given this peer's configured `TestPeerConfig::fork_checkpoints`, a
+        /// neighbor claiming `claimed_view` (its own `(burn_block_height, burn_header_hash)`
+        /// pairs, as it would report them in a handshake) would be refused for being on an
+        /// incompatible fork; it does not panic either way.  Useful for asserting partition behavior in tests even though the
+        /// actual handshake-path enforcement isn't wired up in this snapshot (see
+        /// `check_fork_checkpoints`).
+        pub fn assert_rejects_fork(&self, claimed_view: &[(u64, BurnchainHeaderHash)]) -> bool {
+            match check_fork_checkpoints(&self.config.fork_checkpoints, claimed_view) {
+                Err(Error::Banned(ReasonForBan::IncompatibleFork)) => true,
+                _ => false
+            }
+        }
+
+        /// Whether this peer (per its configured `simulated_rc_consensus_hash`) should defer
+        /// full block/microblock exchange with a neighbor reporting `their_rc_consensus_hash`.
+        /// If this peer has no override configured, there's nothing to compare against, so
+        /// exchange is never deferred on this basis.
+        pub fn defers_exchange_with(&self, their_rc_consensus_hash: &ConsensusHash) -> bool {
+            match self.config.simulated_rc_consensus_hash {
+                Some(ref ours) => reward_cycle_compatibility(ours, their_rc_consensus_hash) == RewardCycleCompatibility::Divergent,
+                None => false
+            }
+        }
+    }
+
+    /// Simulated network conditions on one directed edge `(from_index, to_index)` of a
+    /// `TestPeerNetwork`'s topology.  `drop_rate` (in `[0.0, 1.0]`) is the chance the edge is
+    /// never connected at all, simulating a partition; `latency_rounds` holds the connection
+    /// back that many `step_all()` rounds before it's attempted.  Finer, per-message drop/delay
+    /// would require hooking into `net::p2p`'s internal dispatch loop, which isn't part of this
+    /// snapshot.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct SimulatedLinkConfig {
+        pub drop_rate: f64,
+        pub latency_rounds: u32,
+    }
+
+    impl SimulatedLinkConfig {
+        pub fn default() -> SimulatedLinkConfig {
+            SimulatedLinkConfig { drop_rate: 0.0, latency_rounds: 0 }
+        }
+    }
+
+    /// Tiny xorshift64 PRNG used in place of `rand::thread_rng()` so a `TestPeerNetwork`'s
+    /// drop-rate decisions are reproducible across runs given the same seed.
+    struct DeterministicRng {
+        state: u64
+    }
+
+    impl DeterministicRng {
+        fn new(seed: u64) -> DeterministicRng {
+            DeterministicRng { state: if seed == 0 { 0xdeadbeefcafef00d } else { seed } }
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.state ^= self.state << 13;
+            self.state ^= self.state >> 7;
+            self.state ^= self.state << 17;
+            self.state
+        }
+
+        fn next_f64(&mut self) -> f64 {
+            (self.next_u64() >> 11) as f64 * (1.0 / ((1u64 << 53) as f64))
+        }
+    }
+
+    /// Deterministic, single-threaded harness that wires several `TestPeer`s into a fixed
+    /// topology and drives them in lockstep.  The topology is read directly off each peer's
+    /// `TestPeerConfig::initial_neighbors`: an edge exists from peer `i` to peer `j` whenever
+    /// peer `j`'s `to_neighbor().addr` appears in peer `i`'s `initial_neighbors`.  `step_all()`
+    /// advances every peer exactly one round, in a fixed index order, so a test that seeds its
+    /// peers the same way gets the same sequence of `NetworkResult`s every run -- no
+    /// `thread::spawn` or wall-clock sleeps are involved anywhere in this harness, unlike
+    /// `dns_thread_start`.
+    pub struct TestPeerNetwork {
+        pub peers: Vec<TestPeer>,
+        adjacency: Vec<Vec<usize>>,
+        links: HashMap<(usize, usize), SimulatedLinkConfig>,
+        pending_connects: Vec<(usize, usize, u32)>,
+        rng: DeterministicRng,
+    }
+
+    impl TestPeerNetwork {
+        pub fn new(peers: Vec<TestPeer>, seed: u64) -> TestPeerNetwork {
+            let adjacency = TestPeerNetwork::build_adjacency(&peers);
+            TestPeerNetwork {
+                peers: peers,
+                adjacency: adjacency,
+                links: HashMap::new(),
+                pending_connects: vec![],
+                rng: DeterministicRng::new(seed),
+            }
+        }
+
+        fn build_adjacency(peers: &Vec<TestPeer>) -> Vec<Vec<usize>> {
+            let keys: Vec<NeighborKey> = peers.iter().map(|p| p.to_neighbor().addr).collect();
+            let mut adjacency = vec![vec![]; peers.len()];
+            for (i, peer) in peers.iter().enumerate() {
+                for neighbor in peer.config.initial_neighbors.iter() {
+                    if let Some(j) = keys.iter().position(|k| k == &neighbor.addr) {
+                        adjacency[i].push(j);
+                    }
+                }
+            }
+            adjacency
+        }
+
+        /// Configure simulated drop/latency on the directed edge from peer `from` to peer `to`.
+        pub fn set_link(&mut self, from: usize, to: usize, link: SimulatedLinkConfig) {
+            self.links.insert((from, to), link);
+        }
+
+        fn link_for(&self, from: usize, to: usize) -> SimulatedLinkConfig {
+            self.links.get(&(from, to)).cloned().unwrap_or(SimulatedLinkConfig::default())
+        }
+
+        /// Connect every peer to its adjacency-graph neighbors, honoring each edge's configured
+        /// `drop_rate` (the edge is never connected) and `latency_rounds` (the edge's connection
+        /// attempt is held back that many `step_all()` rounds).
+        pub fn connect_topology(&mut self) -> Result<(), net_error> {
+            for i in 0..self.peers.len() {
+                let targets = self.adjacency[i].clone();
+                for j in targets {
+                    let link = self.link_for(i, j);
+                    if self.rng.next_f64() < link.drop_rate {
+                        continue;
+                    }
+                    if link.latency_rounds == 0 {
+                        let neighbor_addr = self.peers[j].to_neighbor().addr;
+                        self.peers[i].network.connect_peer(&neighbor_addr).and_then(|_| Ok(()))?;
+                    }
+                    else {
+                        self.pending_connects.push((i, j, link.latency_rounds));
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        /// Advance every peer one round, in index order.  Any deferred (latency-delayed)
+        /// connections scheduled by `connect_topology()` are ticked down and attempted once
+        /// their delay elapses.
+        pub fn step_all(&mut self) -> Vec<NetworkResult> {
+            let pending = self.pending_connects.drain(..).collect::<Vec<_>>();
+            for (i, j, rounds_left) in pending {
+                if rounds_left <= 1 {
+                    let neighbor_addr = self.peers[j].to_neighbor().addr;
+                    let _ = self.peers[i].network.connect_peer(&neighbor_addr);
+                }
+                else {
+                    self.pending_connects.push((i, j, rounds_left - 1));
+                }
+            }
+
+            let mut results = vec![];
+            for i in 0..self.peers.len() {
+                let result = self.peers[i].step().unwrap_or(NetworkResult::new());
+                results.push(result);
+            }
+            results
+        }
+
+        pub fn dump_frontiers(&self) -> () {
+            for (i, peer) in self.peers.iter().enumerate() {
+                // use the opt-in redacted view here (see `Redact`) so a frontier dump over a
+                // whole `TestPeerNetwork` doesn't leak every simulated peer's raw address into
+                // the log by default.
+                info!("--- peer {} frontier ({}) ---", i, peer.to_neighbor().addr.redacted());
+                peer.dump_frontier();
+            }
+        }
+    }
+
+    #[test]
+    fn streaming_reader_enforces_budget() {
+        let data = vec![0x41; 16];
+        let mut reader = StreamingReader::new(&data[..], 10);
+
+        assert!(reader.check_budget(10).is_ok());
+        assert!(reader.check_budget(11).is_err());
+
+        let mut buf = [0u8; 10];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(reader.bytes_read(), 10);
+        assert_eq!(reader.bytes_remaining(), 0);
+        assert!(reader.check_budget(1).is_err());
+
+        // the next byte would push us over budget, so the Read impl itself must fail closed
+        // even though the underlying `Vec<u8>` has more bytes to give.
+        let mut one_more = [0u8; 1];
+        assert!(reader.read_exact(&mut one_more).is_err());
+    }
+
+    #[test]
+    fn compact_filter_build_and_contains() {
+        let block_header_hash = BlockHeaderHash([0x33; 32]);
+        let present = vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 9]];
+        let absent = vec![0xff, 0xee, 0xdd];
+
+        let filter = CompactFilter::build(&block_header_hash, &present);
+        for element in present.iter() {
+            assert!(filter.contains(&block_header_hash, element));
+        }
+        assert!(!filter.contains(&block_header_hash, &absent));
+
+        // the filter is keyed by block header hash, so looking it up under a different key
+        // must not spuriously match.
+        let other_block_header_hash = BlockHeaderHash([0x44; 32]);
+        assert!(!filter.contains(&other_block_header_hash, &present[0]));
+    }
+
+    #[test]
+    fn unframe_payload_rejects_decompression_bomb() {
+        let payload = vec![0x5a; 8 * COMPRESSION_MIN_PAYLOAD_LEN];
+        let framed = frame_payload(&payload);
+        assert_eq!(framed[0], PayloadFraming::Zlib as u8);
+
+        // the receiver claims (via Preamble::payload_len) a much smaller inflated size than the
+        // frame actually decompresses to -- unframe_payload must fail instead of allocating the
+        // full inflated payload.
+        let max_inflated_len = payload.len() / 4;
+        match unframe_payload(&framed, max_inflated_len) {
+            Err(Error::OverflowError(..)) => (),
+            other => panic!("expected an OverflowError, got {:?}", other),
+        }
+
+        // a generous enough budget round-trips correctly.
+        let inflated = unframe_payload(&framed, payload.len()).unwrap();
+        assert_eq!(inflated, payload);
+    }
+
+    #[test]
+    fn noise_handshake_round_trip() {
+        let initiator_s = NoiseKeypair::generate();
+        let responder_s = NoiseKeypair::generate();
+        let initiator_s_pubkey = initiator_s.public_bytes();
+        let responder_s_pubkey = responder_s.public_bytes();
+        let prologue = b"stacks-p2p-noise-xx-v1";
+
+        let mut initiator = NoiseHandshake::new_initiator(initiator_s, prologue);
+        let mut responder = NoiseHandshake::new_responder(responder_s, prologue);
+
+        let msg1 = initiator.write_message_1();
+        responder.read_message_1(&msg1).unwrap();
+
+        let msg2 = responder.write_message_2().unwrap();
+        initiator.read_message_2(&msg2).unwrap();
+
+        let msg3 = initiator.write_message_3().unwrap();
+        responder.read_message_3(&msg3).unwrap();
+
+        let (initiator_saw_responder_key, mut initiator_keys) = initiator.finish().unwrap();
+        let (responder_saw_initiator_key, mut responder_keys) = responder.finish().unwrap();
+
+        // each side should have authenticated the other's real static key, not an ephemeral one.
+        assert_eq!(initiator_saw_responder_key, responder_s_pubkey);
+        assert_eq!(responder_saw_initiator_key, initiator_s_pubkey);
+
+        // the transport keys are cross-wired: what the initiator encrypts with `send`, the
+        // responder must decrypt with `recv`, and vice versa.
+        let plaintext = b"hello from the initiator";
+        let ciphertext = initiator_keys.encrypt_record(plaintext);
+        let decrypted = responder_keys.decrypt_record(&ciphertext).unwrap();
+        assert_eq!(decrypted, plaintext);
+
+        let reply = b"hello from the responder";
+        let reply_ciphertext = responder_keys.encrypt_record(reply);
+        let reply_decrypted = initiator_keys.decrypt_record(&reply_ciphertext).unwrap();
+        assert_eq!(reply_decrypted, reply);
+
+        // a tampered ciphertext must fail to decrypt rather than silently returning garbage.
+        let mut tampered = initiator_keys.encrypt_record(b"tamper me");
+        let last = tampered.len() - 1;
+        tampered[last] ^= 0x01;
+        assert!(responder_keys.decrypt_record(&tampered).is_err());
     }
 }